@@ -44,6 +44,7 @@ fn main() {
         .boost_rounds(2)                         // number of training iterations
         .booster_params(booster_params)          // model parameters
         .evaluation_sets(Some(&evaluation_sets)) // optional datasets to evaluate against in each iteration
+        .print_eval(true)                        // print evaluation results to stdout
         .build().unwrap();
 
     // train booster model, and print evaluation metrics
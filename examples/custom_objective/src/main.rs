@@ -55,6 +55,7 @@ fn main() {
         .booster_params(booster_params)
         .boost_rounds(2)
         .evaluation_sets(Some(&evaluation_sets))
+        .print_eval(true)
         .custom_objective_fn(Some(log_reg_obj))
         .custom_evaluation_fn(Some(eval_error))
         .build().unwrap();
@@ -40,6 +40,7 @@ fn main() {
         .boost_rounds(4)
         .booster_params(booster_params)
         .evaluation_sets(Some(&evaluation_sets))
+        .print_eval(true)
         .build().unwrap();
 
     // Train booster model, and print evaluation metrics
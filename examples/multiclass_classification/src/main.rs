@@ -49,6 +49,7 @@ fn main() {
         .booster_params(booster_params)
         .boost_rounds(5)
         .evaluation_sets(Some(eval_sets))
+        .print_eval(true)
         .build().unwrap();
 
     // train a new booster model with given parameters, printing results on evaluation sets
@@ -0,0 +1,69 @@
+//! Helpers for passing arrays to and from XGBoost using the
+//! [array interface](https://numpy.org/doc/stable/reference/arrays.interface.html) protocol.
+
+use std::{mem, slice};
+
+use serde_json::{self, Value};
+
+use super::{XGBResult, XGBError};
+
+/// Describe a contiguous row-major array with given shape as a JSON encoded array interface.
+///
+/// The returned description borrows `data`'s memory, so must not outlive it.
+pub(crate) fn describe_array<T>(data: &[T], shape: &[usize], typestr: &str) -> String {
+    assert_eq!(data.len(), shape.iter().product::<usize>());
+    let shape: Vec<String> = shape.iter().map(|dim| dim.to_string()).collect();
+    format!(r#"{{"data": [{}, true], "shape": [{}], "typestr": "{}", "version": 3}}"#,
+            data.as_ptr() as usize, shape.join(", "), typestr)
+}
+
+/// Copy the contents of an array described by a JSON encoded array interface.
+///
+/// `typestr` is the expected array interface type string (e.g. `<f4`), and must match the type `T`.
+pub(crate) fn read_array<T: Copy>(interface: &str, typestr: &str) -> XGBResult<Vec<T>> {
+    let interface: Value = serde_json::from_str(interface)
+        .map_err(|err| XGBError::new(format!("Unable to parse array interface: {}", err)))?;
+
+    let actual_typestr = interface["typestr"].as_str().unwrap_or("");
+    if actual_typestr != typestr {
+        return Err(XGBError::new(format!("Unexpected array interface type '{}', expected '{}'",
+                                         actual_typestr, typestr)));
+    }
+    assert_eq!(typestr[2..].parse::<usize>().ok(), Some(mem::size_of::<T>()));
+
+    let len = match interface["shape"].as_array() {
+        Some(shape) => shape.iter().map(|dim| dim.as_u64().unwrap_or(0) as usize).product(),
+        None        => return Err(XGBError::new("Array interface is missing shape")),
+    };
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let address = match interface["data"][0].as_u64() {
+        Some(address) if address != 0 => address as usize,
+        _ => return Err(XGBError::new("Array interface is missing data pointer")),
+    };
+    Ok(unsafe { slice::from_raw_parts(address as *const T, len).to_vec() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_and_read_array() {
+        let data = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let interface = describe_array(&data, &[2, 3], "<f4");
+        assert!(interface.contains(r#""shape": [2, 3]"#));
+        assert_eq!(read_array::<f32>(&interface, "<f4").unwrap(), data.to_vec());
+    }
+
+    #[test]
+    fn read_float_array() {
+        let data = [1.0f32, 2.5, -3.0];
+        let interface = format!(r#"{{"data": [{}, true], "shape": [3], "typestr": "<f4", "version": 3}}"#,
+                                data.as_ptr() as usize);
+        assert_eq!(read_array::<f32>(&interface, "<f4").unwrap(), data.to_vec());
+        assert!(read_array::<u64>(&interface, "<u8").is_err());
+    }
+}
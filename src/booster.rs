@@ -1,9 +1,14 @@
 use libc;
-use std::{fs::File, fmt, slice, ffi, ptr};
+use std::{fs::{self, File}, fmt, slice, ffi, ptr};
 use std::str::FromStr;
-use std::io::{self, Write, BufReader, BufRead};
-use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write, BufReader, BufRead, BufWriter};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use error::XGBError;
 use dmatrix::DMatrix;
 use std::os::unix::ffi::OsStrExt;
@@ -11,38 +16,40 @@ use std::os::unix::ffi::OsStrExt;
 use xgboost_sys;
 use tempfile;
 use indexmap::IndexMap;
+use serde_json;
 
 use super::XGBResult;
+use array_interface;
+use forest::{Forest, Link, Node, Tree};
 use parameters::{BoosterParameters, TrainingParameters};
+use parameters::learning::{Metrics, Objective};
+use parameters::tree::Predictor;
 
 pub type CustomObjective = fn(&[f32], &DMatrix) -> (Vec<f32>, Vec<f32>);
 
-/// Used to control the return type of predictions made by C Booster API.
-enum PredictOption {
-    OutputMargin,
-    PredictLeaf,
-    PredictContribitions,
-    //ApproximateContributions,
-    PredictInteractions,
-}
+/// Attribute holding a JSON list of feature names, as XGBoost's binary model format doesn't store them.
+static KEY_FEATURE_NAMES_ATTR: &'static str = "feature_names";
 
-impl PredictOption {
-    /// Convert list of options into a bit mask.
-    fn options_as_mask(options: &[PredictOption]) -> i32 {
-        let mut option_mask = 0x00;
-        for option in options {
-            let value = match *option {
-                PredictOption::OutputMargin => 0x01,
-                PredictOption::PredictLeaf => 0x02,
-                PredictOption::PredictContribitions => 0x04,
-                //PredictOption::ApproximateContributions => 0x08,
-                PredictOption::PredictInteractions => 0x10,
-            };
-            option_mask |= value;
-        }
+/// Raw handles of a booster and the matrices it predicts on, shared with worker threads by `predict_chunked`.
+///
+/// XGBoost's prediction functions are thread safe, so these can be used from several threads at once, as long as
+/// the `Booster` and `DMatrix` values owning them outlive the threads and aren't modified meanwhile.
+struct PredictHandles {
+    booster: xgboost_sys::BoosterHandle,
+    dmats: Vec<xgboost_sys::DMatrixHandle>,
+}
 
-        option_mask
-    }
+unsafe impl Send for PredictHandles {}
+unsafe impl Sync for PredictHandles {}
+
+/// Type of output requested from `XGBoosterPredictFromDMatrix`.
+#[derive(Clone, Copy)]
+enum PredictType {
+    Value = 0,
+    Margin = 1,
+    Contributions = 2,
+    Interactions = 4,
+    Leaf = 6,
 }
 
 /// Core model in XGBoost, containing functions for training, evaluating and predicting.
@@ -54,11 +61,35 @@ impl PredictOption {
 /// [`new_with_cached_dmats`](struct.Booster.html#method.new_with_cached_dmats), then trained by calling
 /// [`update`](struct.Booster.html#method.update) or [`update_custom`](struct.Booster.html#method.update_custom)
 /// in a loop.
+///
+/// # Ownership
+///
+/// Matrices are borrowed for the duration of each call (e.g. `update` or `predict`), so can't be dropped
+/// while in use. XGBoost keeps its own reference to any cached matrices, so dropping a `DMatrix` passed to
+/// `new_with_cached_dmats` is safe. To keep using cached matrices through the Booster itself, e.g. to
+/// predict on training data without holding onto it separately, create it with
+/// [`new_with_shared_dmats`](struct.Booster.html#method.new_with_shared_dmats), which keeps them alive for
+/// as long as the Booster.
 pub struct Booster {
     handle: xgboost_sys::BoosterHandle,
+    default_iteration_range: Option<(u32, u32)>,
+    shared_dmats: Vec<Rc<DMatrix>>,
+    eval_history: BTreeMap<String, Vec<f32>>,
+    round_timings: Vec<Duration>,
 }
 
 impl Booster {
+    /// Wrap a BoosterHandle created by the XGBoost C API.
+    fn from_handle(handle: xgboost_sys::BoosterHandle) -> Self {
+        Booster {
+            handle,
+            default_iteration_range: None,
+            shared_dmats: Vec::new(),
+            eval_history: BTreeMap::new(),
+            round_timings: Vec::new(),
+        }
+    }
+
     /// Create a new Booster model with given parameters.
     ///
     /// This model can then be trained using calls to update/boost as appropriate.
@@ -74,36 +105,132 @@ impl Booster {
     /// Cached DMatrix can sometimes be used internally by XGBoost to speed up certain operations.
     pub fn new_with_cached_dmats(params: &BoosterParameters, dmats: &[&DMatrix]) -> XGBResult<Self> {
         let mut handle = ptr::null_mut();
-        // TODO: check this is safe if any dmats are freed
+        // XGBoost holds its own references to cached matrices, so they're safe to free before the booster
         let s: Vec<xgboost_sys::DMatrixHandle> = dmats.iter().map(|x| x.handle).collect();
         xgb_call!(xgboost_sys::XGBoosterCreate(s.as_ptr(), dmats.len() as u64, &mut handle))?;
 
-        let mut booster = Booster { handle };
+        let mut booster = Booster::from_handle(handle);
         booster.set_params(params)?;
+
+        // remember the training matrix's feature names and types, as XGBoost only reads them from the booster
+        if let Some(dmat) = dmats.first() {
+            let feature_names = dmat.get_feature_names()?;
+            if !feature_names.is_empty() {
+                booster.set_str_feature_info("feature_name", &feature_names)?;
+                let names_json = serde_json::to_string(&feature_names).unwrap();
+                booster.set_attribute(KEY_FEATURE_NAMES_ATTR, &names_json)?;
+            }
+            let feature_types = dmat.get_feature_types()?;
+            if !feature_types.is_empty() {
+                booster.set_str_feature_info("feature_type", &feature_types)?;
+            }
+        }
+        Ok(booster)
+    }
+
+    /// Restore feature names stored as an attribute by
+    /// [`new_with_cached_dmats`](struct.Booster.html#method.new_with_cached_dmats), if the loaded model
+    /// format didn't keep them itself.
+    fn restore_feature_names(&mut self) -> XGBResult<()> {
+        if !self.feature_names()?.is_empty() {
+            return Ok(());
+        }
+        if let Some(names_json) = self.get_attribute(KEY_FEATURE_NAMES_ATTR)? {
+            let feature_names: Vec<String> = serde_json::from_str(&names_json)
+                .map_err(|err| XGBError::new(format!("Unable to parse stored feature names: {}", err)))?;
+            self.set_str_feature_info("feature_name", &feature_names)?;
+        }
+        Ok(())
+    }
+
+    /// Create a new Booster model with given parameters and list of DMatrix to cache, keeping the matrices alive
+    /// for as long as the Booster.
+    ///
+    /// The matrices can be accessed again through
+    /// [`shared_dmats`](struct.Booster.html#method.shared_dmats).
+    pub fn new_with_shared_dmats(params: &BoosterParameters, dmats: &[Rc<DMatrix>]) -> XGBResult<Self> {
+        let refs: Vec<&DMatrix> = dmats.iter().map(|dmat| dmat.as_ref()).collect();
+        let mut booster = Booster::new_with_cached_dmats(params, &refs)?;
+        booster.shared_dmats = dmats.to_vec();
         Ok(booster)
     }
 
+    /// Get the matrices this Booster was created with by
+    /// [`new_with_shared_dmats`](struct.Booster.html#method.new_with_shared_dmats).
+    pub fn shared_dmats(&self) -> &[Rc<DMatrix>] {
+        &self.shared_dmats
+    }
+
     /// Save this Booster as a binary file at given path.
+    ///
+    /// XGBoost picks the output format from the file extension: paths ending in `.json` are saved as JSON,
+    /// paths ending in `.ubj` as [UBJSON](https://ubjson.org/), and anything else in XGBoost's binary format.
+    /// Use [`save_json`](struct.Booster.html#method.save_json) or [`save_ubj`](struct.Booster.html#method.save_ubj)
+    /// to choose the format explicitly.
     pub fn save<P: AsRef<Path>>(&self, path: P) -> XGBResult<()> {
         debug!("Writing Booster to: {}", path.as_ref().display());
-        let fname = ffi::CString::new(path.as_ref().as_os_str().as_bytes()).unwrap();
+        let fname = ffi::CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|_| XGBError::InvalidPath(path.as_ref().to_path_buf()))?;
         xgb_call!(xgboost_sys::XGBoosterSaveModel(self.handle, fname.as_ptr()))
     }
 
-    /// Load a Booster from a binary file at given path.
+    /// Save this Booster as a JSON file at given path, regardless of the path's extension.
+    ///
+    /// Unlike the binary format, JSON models can be loaded by other versions of XGBoost.
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> XGBResult<()> {
+        debug!("Writing Booster as JSON to: {}", path.as_ref().display());
+        let bytes = self.save_to_buffer("json")?;
+        fs::write(path, bytes).map_err(|err| XGBError::new(err.to_string()))
+    }
+
+    /// Save this Booster as a [UBJSON](https://ubjson.org/) file at given path, regardless of the path's
+    /// extension.
+    ///
+    /// UBJSON is a more compact binary encoding of the JSON model, and is similarly portable across
+    /// XGBoost versions.
+    pub fn save_ubj<P: AsRef<Path>>(&self, path: P) -> XGBResult<()> {
+        debug!("Writing Booster as UBJSON to: {}", path.as_ref().display());
+        let bytes = self.save_to_buffer("ubj")?;
+        fs::write(path, bytes).map_err(|err| XGBError::new(err.to_string()))
+    }
+
+    /// Serialise this Booster into a buffer using given XGBoost model format (`json`, `ubj` or `deprecated`).
+    pub(crate) fn save_to_buffer(&self, format: &str) -> XGBResult<Vec<u8>> {
+        let config = ffi::CString::new(format!("{{\"format\": \"{}\"}}", format)).unwrap();
+        let mut out_len = 0;
+        let mut out_dptr = ptr::null();
+        xgb_call!(xgboost_sys::XGBoosterSaveModelToBuffer(self.handle, config.as_ptr(), &mut out_len, &mut out_dptr))?;
+        assert!(!out_dptr.is_null());
+        Ok(unsafe { slice::from_raw_parts(out_dptr as *const u8, out_len as usize).to_vec() })
+    }
+
+    /// Load a Booster from a file at given path.
+    ///
+    /// As with [`save`](struct.Booster.html#method.save), the format is inferred from the file extension
+    /// (`.json`, `.ubj`, or XGBoost's binary format otherwise).
+    ///
+    /// This includes `.json` models saved by XGBoost's Python package (e.g. by `Booster.save_model` or an
+    /// `XGBClassifier`), which predict the same as in Python. Recent versions of XGBoost estimate `base_score`
+    /// from the training data, and the estimated value is stored in the model, so avoid calling
+    /// [`set_params`](struct.Booster.html#method.set_params) on a loaded model with default parameters, as
+    /// that overwrites it with the default `base_score` of 0.5. Check the loaded value with
+    /// [`base_score`](struct.Booster.html#method.base_score).
     pub fn load<P: AsRef<Path>>(path: P) -> XGBResult<Self> {
         debug!("Loading Booster from: {}", path.as_ref().display());
 
         // gives more control over error messages, avoids stack trace dump from C++
         if !path.as_ref().exists() {
-            return Err(XGBError::new(format!("File not found: {}", path.as_ref().display())));
+            return Err(XGBError::InvalidPath(path.as_ref().to_path_buf()));
         }
 
-        let fname = ffi::CString::new(path.as_ref().as_os_str().as_bytes()).unwrap();
+        let fname = ffi::CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|_| XGBError::InvalidPath(path.as_ref().to_path_buf()))?;
         let mut handle = ptr::null_mut();
         xgb_call!(xgboost_sys::XGBoosterCreate(ptr::null(), 0, &mut handle))?;
         xgb_call!(xgboost_sys::XGBoosterLoadModel(handle, fname.as_ptr()))?;
-        Ok(Booster { handle })
+        let mut bst = Booster::from_handle(handle);
+        bst.restore_feature_names()?;
+        Ok(bst)
     }
 
     /// Load a Booster directly from a buffer.
@@ -113,7 +240,9 @@ impl Booster {
         let mut handle = ptr::null_mut();
         xgb_call!(xgboost_sys::XGBoosterCreate(ptr::null(), 0, &mut handle))?;
         xgb_call!(xgboost_sys::XGBoosterLoadModelFromBuffer(handle, bytes.as_ptr() as *const _, bytes.len() as u64))?;
-        Ok(Booster { handle })
+        let mut bst = Booster::from_handle(handle);
+        bst.restore_feature_names()?;
+        Ok(bst)
     }
 
     /// Convenience function for creating/training a new Booster.
@@ -122,7 +251,7 @@ impl Booster {
     ///
     /// 1. create a new Booster model with given parameters
     /// 2. train the model with given DMatrix
-    /// 3. print out evaluation results for each training round
+    /// 3. report evaluation results for each training round (see `verbose_eval`)
     /// 4. return trained Booster
     ///
     /// * `params` - training parameters
@@ -130,6 +259,9 @@ impl Booster {
     /// * `num_boost_round` - number of training iterations
     /// * `eval_sets` - list of datasets to evaluate after each boosting round
     pub fn train(params: &TrainingParameters) -> XGBResult<Self> {
+        Booster::validate_labels(params)?;
+        Booster::check_metric_compatibility(params)?;
+
         let cached_dmats = {
             let mut dmats = vec![params.dtrain];
             if let Some(eval_sets) = params.evaluation_sets {
@@ -152,10 +284,31 @@ impl Booster {
         let start_iteration = version / 2;
         //let mut nboost = start_iteration;
 
+        let has_evaluation_sets = match params.evaluation_sets {
+            Some(sets) => !sets.is_empty(),
+            None       => false,
+        };
+        if params.early_stopping_rounds.is_some() && !has_evaluation_sets {
+            return Err(XGBError::InvalidParameter("early stopping requires at least one evaluation set".to_owned()));
+        }
+        let mut best: Option<(i32, f32)> = None;
+        let mut eval_history: BTreeMap<String, Vec<f32>> = BTreeMap::new();
+        let mut round_timings = Vec::new();
+
+        let start_time = Instant::now();
+
         for i in start_iteration..params.boost_rounds as i32 {
+            if let Some(ref cancel) = params.cancel {
+                if cancel.load(Ordering::SeqCst) {
+                    debug!("Training cancelled before round: {}", i);
+                    break;
+                }
+            }
+
             // distributed code: need to resume to this point
             // skip first update if a recovery step
             if version % 2 == 0 {
+                let round_start = Instant::now();
                 if let Some(objective_fn) = params.custom_objective_fn {
                     debug!("Boosting in round: {}", i);
                     bst.update_custom(params.dtrain, objective_fn)?;
@@ -163,13 +316,24 @@ impl Booster {
                     debug!("Updating in round: {}", i);
                     bst.update(params.dtrain, i)?;
                 }
+                round_timings.push(round_start.elapsed());
                 bst.save_rabit_checkpoint()?;
             }
 
+            // XGBoost only picks a concrete method for tree_method=auto once training has started
+            if i == start_iteration {
+                if let Some((configured, resolved)) = bst.tree_methods()? {
+                    if configured == "auto" {
+                        info!("tree_method=auto resolved to: {}", resolved);
+                    }
+                }
+            }
+
             assert!(unsafe { xgboost_sys::RabitGetWorldSize() == 1 || version == xgboost_sys::RabitVersionNumber() });
 
             //nboost += 1;
 
+            let mut metrics = Vec::new();
             if let Some(eval_sets) = params.evaluation_sets {
                 let mut dmat_eval_results = bst.eval_set(eval_sets, i)?;
 
@@ -178,9 +342,15 @@ impl Booster {
                     for (dmat, dmat_name) in eval_sets {
                         let margin = bst.predict_margin(dmat)?;
                         let eval_result = eval_fn(&margin, dmat);
-                        let eval_results = dmat_eval_results.entry(eval_name.to_string())
+                        let eval_results = dmat_eval_results.entry(dmat_name.to_string())
                             .or_insert_with(IndexMap::new);
-                        eval_results.insert(dmat_name.to_string(), eval_result);
+                        eval_results.insert(eval_name.to_string(), eval_result);
+                    }
+                }
+
+                for (dmat_name, eval_results) in &dmat_eval_results {
+                    for (eval_name, result) in eval_results {
+                        metrics.push((format!("{}-{}", dmat_name, eval_name), *result));
                     }
                 }
 
@@ -193,19 +363,206 @@ impl Booster {
                     }
                 }
 
-                print!("[{}]", i);
-                for (eval_name, dmat_results) in eval_dmat_results {
-                    for (dmat_name, result) in dmat_results {
-                        print!("\t{}-{}:{}", dmat_name, eval_name, result);
+                let report_round = match params.verbose_eval {
+                    Some(period) if period > 0 => (i - start_iteration) % period as i32 == 0,
+                    _                          => false,
+                };
+                if report_round {
+                    let mut line = format!("[{}]", i);
+                    for (eval_name, dmat_results) in eval_dmat_results {
+                        for (dmat_name, result) in dmat_results {
+                            line.push_str(&format!("\t{}-{}:{}", dmat_name, eval_name, result));
+                        }
+                    }
+                    if params.print_eval {
+                        println!("{}", line);
+                    }
+                    info!("{}", line);
+                }
+            }
+
+            for (name, score) in &metrics {
+                eval_history.entry(name.clone()).or_default().push(*score);
+            }
+
+            // early stopping is decided by the last metric of the last evaluation set
+            let last_metric = metrics.last().cloned();
+
+            if let Some(progress_fn) = params.progress_fn {
+                progress_fn(&IterationReport { round: i as u32, metrics, elapsed: start_time.elapsed() });
+            }
+
+            if let (Some(stopping_rounds), Some((name, score))) = (params.early_stopping_rounds, last_metric) {
+                let maximize = params.early_stopping_maximize
+                    .unwrap_or_else(|| Booster::is_maximised_metric(&name));
+                let improved = match best {
+                    None                 => true,
+                    Some((_, best_score)) => if maximize {
+                        score > best_score
+                    } else {
+                        score < best_score
+                    },
+                };
+                if improved {
+                    best = Some((i, score));
+                } else if let Some((best_iteration, _)) = best {
+                    if i - best_iteration >= stopping_rounds as i32 {
+                        debug!("Stopping early in round {}, best iteration: {}", i, best_iteration);
+                        break;
                     }
                 }
-                println!();
             }
         }
 
+        if let Some((best_iteration, best_score)) = best {
+            bst.set_attribute("best_iteration", &best_iteration.to_string())?;
+            bst.set_attribute("best_score", &best_score.to_string())?;
+            bst.set_default_iteration_range((0, best_iteration as u32 + 1));
+        }
+        bst.eval_history = eval_history;
+        bst.round_timings = round_timings;
+
         Ok(bst)
     }
 
+    /// Train a new Booster as with [`train`](struct.Booster.html#method.train), holding out a random
+    /// `valid_fraction` of `params`' training matrix for validation.
+    ///
+    /// The held out rows are chosen as in [`DMatrix::sample_rows`](struct.DMatrix.html#method.sample_rows) with
+    /// given `seed`, and replace any evaluation sets in `params`. Training and validation results are
+    /// evaluated each round under the names `train` and `valid`, so early stopping (if enabled in `params`)
+    /// is decided by the validation set. Results are available from
+    /// [`eval_history`](struct.Booster.html#method.eval_history) afterwards.
+    pub fn train_with_split(params: &TrainingParameters, valid_fraction: f64, seed: u64) -> XGBResult<Self> {
+        if !(valid_fraction > 0.0 && valid_fraction < 1.0) {
+            let msg = format!("validation fraction {} must be in range (0, 1)", valid_fraction);
+            return Err(XGBError::InvalidParameter(msg));
+        }
+
+        let dmat = params.dtrain;
+        let valid_indices = dmat.sample_row_indices(valid_fraction, seed)?;
+        let mut is_valid = vec![false; dmat.num_rows()];
+        for i in &valid_indices {
+            is_valid[*i] = true;
+        }
+        let train_indices: Vec<usize> = (0..dmat.num_rows()).filter(|i| !is_valid[*i]).collect();
+
+        let dtrain = dmat.slice(&train_indices)?;
+        let dvalid = dmat.slice(&valid_indices)?;
+        let evaluation_sets = [(&dtrain, "train"), (&dvalid, "valid")];
+
+        let mut split_params = params.clone();
+        split_params.dtrain = &dtrain;
+        split_params.evaluation_sets = Some(&evaluation_sets);
+        Booster::train(&split_params)
+    }
+
+    /// Get the evaluation results recorded for each round while this Booster was trained by
+    /// [`train`](struct.Booster.html#method.train).
+    ///
+    /// Maps `<dataset>-<metric>` names (e.g. `valid-rmse`) to one score per boosting round, with a separate
+    /// series for every metric of every evaluation set. Empty if no evaluation sets were used, or the Booster
+    /// wasn't created by training.
+    ///
+    /// Early stopping still only watches the last metric of the last evaluation set.
+    pub fn eval_history(&self) -> &BTreeMap<String, Vec<f32>> {
+        &self.eval_history
+    }
+
+    /// Get the wall-clock time taken to boost each round while this Booster was trained by
+    /// [`train`](struct.Booster.html#method.train), in order.
+    ///
+    /// Only covers the update of the model itself, not evaluation of any evaluation sets. Empty if the
+    /// Booster wasn't created by training.
+    pub fn round_timings(&self) -> &[Duration] {
+        &self.round_timings
+    }
+
+    /// Whether XGBoost treats higher scores as better for given evaluation result name (e.g. `test-auc`).
+    fn is_maximised_metric(name: &str) -> bool {
+        // ranking metrics may have a '-' suffix (e.g. `ndcg@5-`), which changes how empty groups are scored
+        let name = name.trim_end_matches('-');
+        let metric = name.rsplit('-').next().unwrap_or(name);
+        let metric = metric.split('@').next().unwrap_or(metric);
+        ["auc", "aucpr", "map", "ndcg", "pre"].contains(&metric)
+    }
+
+    /// Set the range of boosting rounds used by default when predicting, as `begin..end`.
+    ///
+    /// Applies to [`predict`](struct.Booster.html#method.predict) and
+    /// [`predict_with_shape`](struct.Booster.html#method.predict_with_shape). A range of `(0, 0)` uses every
+    /// round in the model. After training with early stopping, this defaults to
+    /// `(0, best_iteration + 1)`.
+    pub fn set_default_iteration_range(&mut self, iteration_range: (u32, u32)) {
+        self.default_iteration_range = Some(iteration_range);
+    }
+
+    /// Get the best iteration found while training with early stopping, if any.
+    pub fn best_iteration(&self) -> XGBResult<Option<u32>> {
+        match self.get_attribute("best_iteration")? {
+            Some(value) => value.parse::<u32>()
+                .map(Some)
+                .map_err(|_| XGBError::new(format!("Invalid best_iteration attribute: {}", value))),
+            None        => Ok(None),
+        }
+    }
+
+    /// Check training labels are compatible with the learning objective before any boosting happens, as
+    /// otherwise XGBoost fails part way through training with a less helpful error.
+    ///
+    /// Multiclass labels must be class indices, so the first label which is negative or not an integer is
+    /// reported with an `XGBError::InvalidData` error, and a maximum label of `num_class` or more with an
+    /// `XGBError::LabelOutOfRange` error.
+    fn validate_labels(params: &TrainingParameters) -> XGBResult<()> {
+        let num_class = match params.booster_params.learning_params.objective {
+            Objective::MultiSoftmax(n) | Objective::MultiSoftprob(n) => n,
+            _ => return Ok(()),
+        };
+
+        let labels = params.dtrain.get_labels()?;
+        // also catches NaN and infinite labels
+        if let Some(label) = labels.iter().find(|label| !(**label >= 0.0 && label.fract() == 0.0)) {
+            let msg = format!("label {} isn't a class index, multiclass labels must be integers in [0, {})",
+                              label, num_class);
+            return Err(XGBError::InvalidData(msg));
+        }
+        let max_label = labels.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        if max_label >= num_class as f32 {
+            return Err(XGBError::LabelOutOfRange { max_label, num_class });
+        }
+        Ok(())
+    }
+
+    /// Check the evaluation metrics given in the learning parameters suit the learning objective, using
+    /// `INCOMPATIBLE_METRICS`, as otherwise XGBoost reports meaningless scores or unhelpful warnings.
+    ///
+    /// Logs a warning for each unsuitable metric, or fails if `strict_metrics` is set.
+    fn check_metric_compatibility(params: &TrainingParameters) -> XGBResult<()> {
+        let learning_params = &params.booster_params.learning_params;
+        let metrics = match learning_params.eval_metrics {
+            Metrics::Custom(ref metrics) => metrics,
+            Metrics::Auto                => return Ok(()),
+        };
+        let objective = learning_params.objective.to_string();
+        let incompatible = INCOMPATIBLE_METRICS.iter()
+            .find(|(objectives, _)| objectives.contains(&objective.trim_start_matches("gpu:")))
+            .map(|(_, metrics)| *metrics)
+            .unwrap_or(&[]);
+
+        for metric in metrics {
+            let metric = metric.to_string();
+            let name = metric.split('@').next().unwrap_or(&metric).trim_end_matches('-');
+            if incompatible.contains(&name) {
+                let msg = format!("evaluation metric '{}' is not suited to objective '{}'", metric, objective);
+                if params.strict_metrics {
+                    return Err(XGBError::InvalidParameter(msg));
+                }
+                warn!("{}", msg);
+            }
+        }
+        Ok(())
+    }
+
     /// Update this Booster's parameters.
     pub fn set_params(&mut self, p: &BoosterParameters) -> XGBResult<()> {
         for (key, value) in p.as_string_pairs() {
@@ -225,6 +582,95 @@ impl Booster {
         xgb_call!(xgboost_sys::XGBoosterUpdateOneIter(self.handle, iteration, dtrain.handle))
     }
 
+    /// Refresh the leaf values and node statistics of every tree in this model using given data, without
+    /// changing the structure of any tree.
+    ///
+    /// Runs one pass of XGBoost's `refresh` updater (`process_type=update`) over each existing boosting
+    /// round, so the number of trees (and [`num_boosted_rounds`](struct.Booster.html#method.num_boosted_rounds))
+    /// stays the same.
+    ///
+    /// The Booster is left configured for updating existing trees, so parameters should be reset (e.g. with
+    /// [`set_params`](struct.Booster.html#method.set_params)) before boosting any new rounds.
+    pub fn refresh(&mut self, dmat: &DMatrix) -> XGBResult<()> {
+        let num_rounds = self.num_boosted_rounds()?;
+        self.set_param("process_type", "update")?;
+        self.set_param("updater", "refresh")?;
+        self.set_param("refresh_leaf", "1")?;
+        for i in 0..num_rounds {
+            self.update(dmat, i as i32)?;
+        }
+        Ok(())
+    }
+
+    /// Prune every tree in this model so that no tree is deeper than `max_depth`.
+    ///
+    /// Runs XGBoost's `prune` updater (`process_type=update`) over each existing boosting round, collapsing
+    /// splits below the new depth into leaves. Pruning can only reduce the depth of trees: trees already
+    /// shallower than `max_depth` are left unchanged.
+    ///
+    /// As with [`refresh`](struct.Booster.html#method.refresh), the Booster is left configured for updating
+    /// existing trees, so parameters should be reset before boosting any new rounds.
+    pub fn prune(&mut self, max_depth: u32) -> XGBResult<()> {
+        let num_rounds = self.num_boosted_rounds()?;
+
+        // the prune updater doesn't look at the data, but updating still needs a matrix of the right width
+        let mut dmat = DMatrix::from_csr(&[0, 0], &[], &[], Some(self.num_features()?))?;
+        dmat.set_labels(&[0.0])?;
+
+        self.set_param("process_type", "update")?;
+        self.set_param("updater", "prune")?;
+        self.set_param("max_depth", &max_depth.to_string())?;
+        for i in 0..num_rounds {
+            self.update(&dmat, i as i32)?;
+        }
+        Ok(())
+    }
+
+    /// Compute the first and second order gradients of this model's objective for its current predictions on
+    /// given training matrix.
+    ///
+    /// XGBoost doesn't expose the gradients it computes internally, so these are recomputed from the
+    /// objective, the full model's predictions and `dtrain`'s labels (and weights, if set). Called after an
+    /// `update`, this gives the gradients the next boosting round will start from.
+    ///
+    /// Supports the `reg:squarederror`, `reg:logistic`, `binary:logistic` and `binary:logitraw` objectives.
+    pub fn gradients(&self, dtrain: &DMatrix) -> XGBResult<(Vec<f32>, Vec<f32>)> {
+        let config = self.config_json()?;
+        let objective = config["learner"]["objective"]["name"].as_str().unwrap_or("").to_owned();
+
+        let labels = dtrain.get_labels()?;
+        let weights = dtrain.get_weights()?;
+        self.check_feature_count(dtrain)?;
+        let (margin, _shape) = self.predict_from_dmatrix(dtrain, PredictType::Margin, (0, 0))?;
+        if margin.len() != labels.len() {
+            return Err(XGBError::DimensionMismatch { expected: margin.len(), got: labels.len() });
+        }
+
+        let (mut gradient, mut hessian): (Vec<f32>, Vec<f32>) = match objective.as_str() {
+            "reg:squarederror" | "reg:linear" => margin.iter().zip(labels)
+                .map(|(pred, label)| (pred - label, 1.0))
+                .unzip(),
+            "reg:logistic" | "binary:logistic" | "binary:logitraw" => margin.iter().zip(labels)
+                .map(|(margin, label)| {
+                    let prob = 1.0 / (1.0 + (-margin).exp());
+                    (prob - label, (prob * (1.0 - prob)).max(1e-16))
+                })
+                .unzip(),
+            _ => {
+                let msg = format!("computing gradients for objective '{}' is not supported", objective);
+                return Err(XGBError::InvalidParameter(msg));
+            },
+        };
+
+        if !weights.is_empty() {
+            for ((grad, hess), weight) in gradient.iter_mut().zip(hessian.iter_mut()).zip(weights) {
+                *grad *= weight;
+                *hess *= weight;
+            }
+        }
+        Ok((gradient, hessian))
+    }
+
     /// Update this model by training it for one round with a custom objective function.
     pub fn update_custom(&mut self, dtrain: &DMatrix, objective_fn: CustomObjective) -> XGBResult<()> {
         let pred = self.predict(dtrain)?;
@@ -241,9 +687,7 @@ impl Booster {
     /// * `hessian` - second order gradient
     fn boost(&mut self, dtrain: &DMatrix, gradient: &[f32], hessian: &[f32]) -> XGBResult<()> {
         if gradient.len() != hessian.len() {
-            let msg = format!("Mismatch between length of gradient and hessian arrays ({} != {})",
-                              gradient.len(), hessian.len());
-            return Err(XGBError::new(msg));
+            return Err(XGBError::DimensionMismatch { expected: gradient.len(), got: hessian.len() });
         }
         assert_eq!(gradient.len(), hessian.len());
 
@@ -299,6 +743,9 @@ impl Booster {
     ///
     /// See parameter::learning::EvaluationMetric for a full list.
     ///
+    /// If the matrix has instance weights set, XGBoost computes weighted metrics (e.g. a weighted average of
+    /// each row's error). The same applies to evaluation sets during training.
+    ///
     /// Returns a map of evaluation metric name to score.
     pub fn evaluate(&self, dmat: &DMatrix) -> XGBResult<HashMap<String, f32>> {
         let name = "default";
@@ -313,6 +760,31 @@ impl Booster {
         Ok(result)
     }
 
+    /// Evaluate each of given (matrix, name) pairs against this model, using metrics defined in this model's
+    /// parameters as with [`evaluate`](struct.Booster.html#method.evaluate).
+    ///
+    /// Returns a map of dataset name to a map of evaluation metric name to score.
+    pub fn evaluate_sets(&self, evals: &[(&DMatrix, &str)]) -> XGBResult<HashMap<String, HashMap<String, f32>>> {
+        let eval = self.eval_set(evals, 0)?;
+        Ok(eval.into_iter()
+           .map(|(dmat_name, results)| (dmat_name, results.into_iter().collect()))
+           .collect())
+    }
+
+    /// Evaluate each of given (matrix, name) pairs against this model, as with
+    /// [`evaluate_sets`](struct.Booster.html#method.evaluate_sets).
+    ///
+    /// Returns a flat map of `<dataset>-<metric>` names (e.g. `test-rmse`) to score, as printed during training.
+    pub fn evaluate_sets_flat(&self, evals: &[(&DMatrix, &str)]) -> XGBResult<HashMap<String, f32>> {
+        let mut result = HashMap::new();
+        for (dmat_name, results) in self.eval_set(evals, 0)? {
+            for (eval_name, score) in results {
+                result.insert(format!("{}-{}", dmat_name, eval_name), score);
+            }
+        }
+        Ok(result)
+    }
+
     /// Get a string attribute that was previously set for this model.
     pub fn get_attribute(&self, key: &str) -> XGBResult<Option<String>> {
         let key = ffi::CString::new(key).unwrap();
@@ -349,81 +821,107 @@ impl Booster {
         Ok(out_vec)
     }
 
-    /// Predict results for given data.
-    ///
-    /// Returns an array containing one entry per row in the given data.
-    pub fn predict(&self, dmat: &DMatrix) -> XGBResult<Vec<f32>> {
-        let option_mask = PredictOption::options_as_mask(&[]);
-        let ntree_limit = 0;
+    /// Get this model's full internal configuration (all parameters, including defaults chosen by XGBoost)
+    /// as a JSON document.
+    pub fn save_config(&self) -> XGBResult<String> {
         let mut out_len = 0;
-        let mut out_result = ptr::null();
-        xgb_call!(xgboost_sys::XGBoosterPredict(self.handle,
-                                                dmat.handle,
-                                                option_mask,
-                                                ntree_limit,
-                                                0,
-                                                &mut out_len,
-                                                &mut out_result))?;
+        let mut out_str = ptr::null();
+        xgb_call!(xgboost_sys::XGBoosterSaveJsonConfig(self.handle, &mut out_len, &mut out_str))?;
+        assert!(!out_str.is_null());
+        let bytes = unsafe { slice::from_raw_parts(out_str as *const u8, out_len as usize) };
+        String::from_utf8(bytes.to_vec()).map_err(|err| XGBError::new(err.to_string()))
+    }
 
-        assert!(!out_result.is_null());
-        let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
-        Ok(data)
+    /// Parse the output of [`save_config`](struct.Booster.html#method.save_config).
+    fn config_json(&self) -> XGBResult<serde_json::Value> {
+        serde_json::from_str(&self.save_config()?)
+            .map_err(|err| XGBError::new(format!("Unable to parse config: {}", err)))
     }
 
-    /// Predict margin for given data.
+    /// Get the number of values [`predict`](struct.Booster.html#method.predict) returns per row, without
+    /// predicting anything.
     ///
-    /// Returns an array containing one entry per row in the given data.
-    pub fn predict_margin(&self, dmat: &DMatrix) -> XGBResult<Vec<f32>> {
-        let option_mask = PredictOption::options_as_mask(&[PredictOption::OutputMargin]);
-        let ntree_limit = 0;
-        let mut out_len = 0;
-        let mut out_result = ptr::null();
-        xgb_call!(xgboost_sys::XGBoosterPredict(self.handle,
-                                                dmat.handle,
-                                                option_mask,
-                                                ntree_limit,
-                                                1,
-                                                &mut out_len,
-                                                &mut out_result))?;
-        assert!(!out_result.is_null());
-        let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
-        Ok(data)
+    /// This is the number of classes for `multi:softprob`, the number of targets for multi-output models, and
+    /// 1 otherwise (including `multi:softmax`, which predicts a single class per row).
+    pub fn output_dim(&self) -> XGBResult<usize> {
+        let config = self.config_json()?;
+        let learner = &config["learner"];
+        let model_param = |name: &str| -> usize {
+            learner["learner_model_param"][name].as_str()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0)
+        };
+
+        if learner["objective"]["name"].as_str() == Some("multi:softprob") {
+            return Ok(model_param("num_class"));
+        }
+        if learner["objective"]["name"].as_str() == Some("multi:softmax") {
+            return Ok(1);
+        }
+        Ok(model_param("num_target").max(1))
     }
 
-    /// Get predicted leaf index for each sample in given data.
-    ///
-    /// Returns an array of shape (number of samples, number of trees) as tuple of (data, num_rows).
+    /// Get the kind of task this model was trained for, derived from its objective.
     ///
-    /// Note: the leaf index of a tree is unique per tree, so e.g. leaf 1 could be found in both tree 1 and tree 0.
-    pub fn predict_leaf(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize))> {
-        let option_mask = PredictOption::options_as_mask(&[PredictOption::PredictLeaf]);
-        let ntree_limit = 0;
-        let mut out_len = 0;
-        let mut out_result = ptr::null();
-        xgb_call!(xgboost_sys::XGBoosterPredict(self.handle,
-                                                dmat.handle,
-                                                option_mask,
-                                                ntree_limit,
-                                                0,
-                                                &mut out_len,
-                                                &mut out_result))?;
-        assert!(!out_result.is_null());
+    /// Objectives are grouped by prefix: `binary:` and `multi:` objectives are classification (with the number
+    /// of classes taken from the model for `multi:`), `rank:` objectives are ranking, `survival:` objectives
+    /// are survival analysis, and everything else (e.g. `reg:`, `count:poisson`) is regression.
+    pub fn task_type(&self) -> XGBResult<TaskType> {
+        let config = self.config_json()?;
+        let learner = &config["learner"];
+        let objective = learner["objective"]["name"].as_str().unwrap_or("");
+        let objective = objective.trim_start_matches("gpu:");
+
+        let task_type = if objective.starts_with("binary:") {
+            TaskType::Classification { n_classes: 2 }
+        } else if objective.starts_with("multi:") {
+            let num_class = &learner["learner_model_param"]["num_class"];
+            let n_classes = num_class.as_str()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| XGBError::new(format!("Unable to parse num_class from config: {}", num_class)))?;
+            TaskType::Classification { n_classes }
+        } else if objective.starts_with("rank:") {
+            TaskType::Ranking
+        } else if objective.starts_with("survival:") {
+            TaskType::Survival
+        } else {
+            TaskType::Regression
+        };
+        Ok(task_type)
+    }
 
-        let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
-        let num_rows = dmat.num_rows();
-        let num_cols = data.len() / num_rows;
-        Ok((data, (num_rows, num_cols)))
+    /// Get the initial prediction score (global bias) this model starts boosting from, before any
+    /// transformation by the objective function.
+    ///
+    /// Read from the learner parameters in [`save_config`](struct.Booster.html#method.save_config).
+    pub fn base_score(&self) -> XGBResult<f32> {
+        let config = self.config_json()?;
+        let base_score = &config["learner"]["learner_model_param"]["base_score"];
+        base_score.as_str()
+            .and_then(|s| s.parse::<f32>().ok())
+            .ok_or_else(|| XGBError::new(format!("Unable to parse base_score from config: {}", base_score)))
     }
 
-    /// Get feature contributions (SHAP values) for each prediction.
+    /// Predict results for given data.
     ///
-    /// The sum of all feature contributions is equal to the run untransformed margin value of the
-    /// prediction.
+    /// Uses the boosting rounds chosen by
+    /// [`set_default_iteration_range`](struct.Booster.html#method.set_default_iteration_range) if set, or
+    /// every round in the model at the time of the call otherwise. Without a default iteration range, results
+    /// therefore change if training is continued (e.g. with [`update`](struct.Booster.html#method.update));
+    /// use [`predict_at_round`](struct.Booster.html#method.predict_at_round) for results which don't.
     ///
-    /// Returns an array of shape (number of samples, number of features + 1) as a tuple of
-    /// (data, num_rows). The final column contains the bias term.
-    pub fn predict_contributions(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize))> {
-        let option_mask = PredictOption::options_as_mask(&[PredictOption::PredictContribitions]);
+    /// Returns an array containing one entry per row in the given data, or an `XGBError::FeatureCountMismatch`
+    /// error if the data has more columns than the model has features. To predict on data missing some trailing
+    /// features, see [`predict_lenient`](struct.Booster.html#method.predict_lenient).
+    pub fn predict(&self, dmat: &DMatrix) -> XGBResult<Vec<f32>> {
+        self.check_feature_count(dmat)?;
+        if let Some(iteration_range) = self.default_iteration_range {
+            let (data, _shape) = self.predict_from_dmatrix(dmat, PredictType::Value, iteration_range)?;
+            return Ok(data);
+        }
+
+        // plain predicted values, without any of XGBoost's output options (margin, leaf indices, etc.)
+        let option_mask = 0;
         let ntree_limit = 0;
         let mut out_len = 0;
         let mut out_result = ptr::null();
@@ -434,509 +932,3669 @@ impl Booster {
                                                 0,
                                                 &mut out_len,
                                                 &mut out_result))?;
-        assert!(!out_result.is_null());
 
+        assert!(!out_result.is_null());
         let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
-        let num_rows = dmat.num_rows();
-        let num_cols = data.len() / num_rows;
-        Ok((data, (num_rows, num_cols)))
+        Ok(data)
     }
 
-    /// Get SHAP interaction values for each pair of features for each prediction.
+    /// Predict results for given data as with [`predict`](struct.Booster.html#method.predict), but
+    /// tolerating a matrix with fewer columns than the model was trained with.
     ///
-    /// The sum of each row (or column) of the interaction values equals the corresponding SHAP
-    /// value (from `predict_contributions`), and the sum of the entire matrix equals the raw
-    /// untransformed margin value of the prediction.
+    /// If `dmat` has fewer than [`num_features`](struct.Booster.html#method.num_features) columns, it's
+    /// copied with the missing trailing columns added, and every value in them treated as missing.
     ///
-    /// Returns an array of shape (number of samples, number of features + 1, number of features + 1).
-    /// The final row and column contain the bias terms.
-    pub fn predict_interactions(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize, usize))> {
-        let option_mask = PredictOption::options_as_mask(&[PredictOption::PredictInteractions]);
-        let ntree_limit = 0;
-        let mut out_len = 0;
-        let mut out_result = ptr::null();
-        xgb_call!(xgboost_sys::XGBoosterPredict(self.handle,
-                                                dmat.handle,
-                                                option_mask,
-                                                ntree_limit,
-                                                0,
-                                                &mut out_len,
-                                                &mut out_result))?;
-        assert!(!out_result.is_null());
-
-        let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
-        let num_rows = dmat.num_rows();
+    /// **Use with care**: this silently changes predictions for any rows that would have had values in the
+    /// dropped columns, and can't detect columns that were dropped or reordered anywhere else, so should
+    /// only be used when the data is known to be missing exactly the trailing features.
+    pub fn predict_lenient(&self, dmat: &DMatrix) -> XGBResult<Vec<f32>> {
+        let num_features = self.num_features()?;
+        if dmat.num_cols() >= num_features {
+            return self.predict(dmat);
+        }
 
-        let dim = ((data.len() / num_rows) as f64).sqrt() as usize;
-        Ok((data, (num_rows, dim, dim)))
+        debug!("Padding DMatrix from {} to {} columns for prediction", dmat.num_cols(), num_features);
+        self.predict(&dmat.pad_columns(num_features)?)
     }
 
-    /// Get a dump of this model as a string.
+    /// Predict results for given data as with [`predict`](struct.Booster.html#method.predict), in chunks of at
+    /// most `chunk_size` rows predicted concurrently, concatenating the results in row order.
     ///
-    /// * `with_statistics` - whether to include statistics in output dump
-    /// * `feature_map` - if given, map feature IDs to feature names from given map
-    pub fn dump_model(&self, with_statistics: bool, feature_map: Option<&FeatureMap>) -> XGBResult<String> {
-        if let Some(fmap) = feature_map {
-            let tmp_dir = match tempfile::tempdir() {
-                Ok(dir) => dir,
-                Err(err) => return Err(XGBError::new(err.to_string())),
-            };
+    /// XGBoost has no option to configure the block size it uses internally when predicting, so each chunk is
+    /// copied out of `dmat` first. Each chunk is also predicted by XGBoost's own thread pool, so chunks are only
+    /// spread over as many threads as leave room for this model's `nthread` threads each on the available CPUs:
+    /// lower it with [`with_nthread`](struct.Booster.html#method.with_nthread) (e.g. to 1) to predict chunks
+    /// concurrently, as by default XGBoost uses every CPU and chunks are predicted one at a time.
+    pub fn predict_chunked(&self, dmat: &DMatrix, chunk_size: usize) -> XGBResult<Vec<f32>> {
+        if chunk_size == 0 {
+            return Err(XGBError::InvalidParameter("chunk_size must be greater than 0".to_owned()));
+        }
+        if dmat.num_rows() <= chunk_size {
+            return self.predict(dmat);
+        }
 
-            let file_path = tmp_dir.path().join("fmap.txt");
-            let mut file: File = match File::create(&file_path) {
-                Ok(f) => f,
-                Err(err) => return Err(XGBError::new(err.to_string())),
-            };
+        self.check_feature_count(dmat)?;
+        let iteration_range = self.default_iteration_range.unwrap_or((0, 0));
+        let indices: Vec<usize> = (0..dmat.num_rows()).collect();
+        let chunks = indices.chunks(chunk_size)
+            .map(|chunk_indices| dmat.slice(chunk_indices))
+            .collect::<XGBResult<Vec<DMatrix>>>()?;
+
+        let num_cpus = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let threads_per_chunk = match self.nthread()? {
+            0 => num_cpus,
+            nthread => nthread.min(num_cpus),
+        };
+        let num_workers = (num_cpus / threads_per_chunk).max(1).min(chunks.len());
+
+        // `self` and `chunks` outlive the scoped workers, and aren't modified until they're done
+        let handles = PredictHandles { booster: self.handle, dmats: chunks.iter().map(|chunk| chunk.handle).collect() };
+        let next_chunk = AtomicUsize::new(0);
+        let mut chunk_preds: Vec<(usize, XGBResult<Vec<f32>>)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..num_workers)
+                .map(|_| scope.spawn(|| {
+                    let mut preds = Vec::new();
+                    loop {
+                        let i = next_chunk.fetch_add(1, Ordering::Relaxed);
+                        if i >= handles.dmats.len() {
+                            return preds;
+                        }
+                        let result = Booster::predict_from_handles(handles.booster, handles.dmats[i],
+                                                                   PredictType::Value, iteration_range);
+                        preds.push((i, result.map(|(data, _shape)| data)));
+                    }
+                }))
+                .collect();
+            workers.into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_else(|err| panic::resume_unwind(err)))
+                .collect()
+        });
+
+        chunk_preds.sort_by_key(|(i, _)| *i);
+        let mut preds = Vec::with_capacity(dmat.num_rows());
+        for (_i, chunk) in chunk_preds {
+            preds.extend(chunk?);
+        }
+        Ok(preds)
+    }
 
-            for (feature_num, (feature_name, feature_type)) in &fmap.0 {
-                writeln!(file, "{}\t{}\t{}", feature_num, feature_name, feature_type).unwrap();
-            }
+    /// Predict results for given data, along with the shape of the returned (row-major) predictions.
+    ///
+    /// Unlike [`predict`](struct.Booster.html#method.predict), the shape is reported by XGBoost itself (using
+    /// `strict_shape`), so is e.g. `[num_rows, num_classes]` for multiclass models, and `[num_rows, 1]` for
+    /// single output models.
+    pub fn predict_with_shape(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, Vec<usize>)> {
+        self.check_feature_count(dmat)?;
+        self.predict_from_dmatrix(dmat, PredictType::Value, self.default_iteration_range.unwrap_or((0, 0)))
+    }
 
-            self.dump_model_fmap(with_statistics, Some(&file_path))
-        } else {
-            self.dump_model_fmap(with_statistics, None)
+    /// Predict results for given data, as a two dimensional array with one row per row of data.
+    ///
+    /// Columns are the model's outputs, e.g. one per class for `multi:softprob`, or one per quantile for
+    /// quantile regression (in the order given by `quantile_alpha`).
+    ///
+    /// Returns a tuple of (data, (num_rows, num_cols)), with data in row-major order.
+    pub fn predict_2d(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize))> {
+        let (preds, shape) = self.predict_with_shape(dmat)?;
+        let num_rows = shape.first().cloned().unwrap_or(0);
+        let num_cols = shape.iter().skip(1).product();
+        Ok((preds, (num_rows, num_cols)))
+    }
+
+    /// Predict results for given data after each boosting round, like scikit-learn's `staged_predict`.
+    ///
+    /// Returns one prediction array per boosting round in the model (ignoring any
+    /// [default iteration range](struct.Booster.html#method.set_default_iteration_range)), where the `k`th array
+    /// is predicted using only the first `k + 1` rounds. Each array has one entry per row (per output, for models
+    /// with several outputs).
+    pub fn staged_predict(&self, dmat: &DMatrix) -> XGBResult<Vec<Vec<f32>>> {
+        self.check_feature_count(dmat)?;
+        let num_rounds = self.num_boosted_rounds()?;
+        let mut stages = Vec::with_capacity(num_rounds as usize);
+        for end in 1..=num_rounds {
+            let (preds, _shape) = self.predict_from_dmatrix(dmat, PredictType::Value, (0, end))?;
+            stages.push(preds);
         }
+        Ok(stages)
     }
 
-    fn dump_model_fmap(&self, with_statistics: bool, feature_map_path: Option<&PathBuf>) -> XGBResult<String> {
-        let fmap = if let Some(path) = feature_map_path {
-            ffi::CString::new(path.as_os_str().as_bytes()).unwrap()
-        } else {
-            ffi::CString::new("").unwrap()
-        };
-        let format = ffi::CString::new("text").unwrap();
-        let mut out_len = 0;
-        let mut out_dump_array = ptr::null_mut();
-        xgb_call!(xgboost_sys::XGBoosterDumpModelEx(self.handle,
-                                                    fmap.as_ptr(),
-                                                    with_statistics as i32,
-                                                    format.as_ptr(),
-                                                    &mut out_len,
-                                                    &mut out_dump_array))?;
+    /// Predict results for given data using only the first `round` boosting rounds, ignoring any
+    /// [default iteration range](struct.Booster.html#method.set_default_iteration_range).
+    ///
+    /// Unlike [`predict`](struct.Booster.html#method.predict), results stay the same if more rounds are trained
+    /// afterwards. Returns an `XGBError::InvalidParameter` error if `round` is 0 or greater than
+    /// [`num_boosted_rounds`](struct.Booster.html#method.num_boosted_rounds).
+    pub fn predict_at_round(&self, dmat: &DMatrix, round: u32) -> XGBResult<Vec<f32>> {
+        self.check_feature_count(dmat)?;
+        let num_rounds = self.num_boosted_rounds()?;
+        if round == 0 || round > num_rounds {
+            let msg = format!("round {} must be in range [1, {}]", round, num_rounds);
+            return Err(XGBError::InvalidParameter(msg));
+        }
+        let (preds, _shape) = self.predict_from_dmatrix(dmat, PredictType::Value, (0, round))?;
+        Ok(preds)
+    }
 
-        let out_ptr_slice = unsafe { slice::from_raw_parts(out_dump_array, out_len as usize) };
-        let out_vec: Vec<String> = out_ptr_slice.iter()
-            .map(|str_ptr| unsafe { ffi::CStr::from_ptr(*str_ptr).to_str().unwrap().to_owned() })
-            .collect();
+    /// Predict hard class labels for given data.
+    ///
+    /// For binary classification, each predicted probability greater than `threshold` becomes class 1, and
+    /// anything else class 0. For multiclass models predicting probabilities (`multi:softprob`), the most
+    /// probable class of each row is returned instead, and `threshold` is ignored (as it is for
+    /// `multi:softmax`, which already predicts classes).
+    pub fn predict_labels(&self, dmat: &DMatrix, threshold: f32) -> XGBResult<Vec<u32>> {
+        let (preds, (_num_rows, num_cols)) = self.predict_2d(dmat)?;
+        if num_cols > 1 {
+            return Ok(preds.chunks(num_cols)
+                      .map(|row| {
+                          let (class, _) = row.iter()
+                              .enumerate()
+                              .fold((0, f32::NEG_INFINITY), |best, (i, p)| if *p > best.1 { (i, *p) } else { best });
+                          class as u32
+                      })
+                      .collect());
+        }
 
-        assert_eq!(out_len as usize, out_vec.len());
-        Ok(out_vec.join("\n"))
+        let config = self.config_json()?;
+        if config["learner"]["objective"]["name"].as_str() == Some("multi:softmax") {
+            return Ok(preds.iter().map(|class| *class as u32).collect());
+        }
+        Ok(preds.iter().map(|p| if *p > threshold { 1 } else { 0 }).collect())
     }
 
-    pub(crate) fn load_rabit_checkpoint(&self) -> XGBResult<i32> {
-        let mut version = 0;
-        xgb_call!(xgboost_sys::XGBoosterLoadRabitCheckpoint(self.handle, &mut version))?;
-        Ok(version)
+    /// Get feature contributions (SHAP values) for a single dense row of features.
+    ///
+    /// Convenience for calling [`predict_contributions`](struct.Booster.html#method.predict_contributions) when
+    /// only one prediction needs explaining. `features` must contain one value per feature in the model, with
+    /// values equal to `missing` (which may be NaN) treated as missing.
+    ///
+    /// Uses the same boosting rounds as [`predict`](struct.Booster.html#method.predict). Returns
+    /// `num_features + 1` contributions (per output group, for multiclass models), with the bias term last.
+    pub fn explain_row(&self, features: &[f32], missing: f32) -> XGBResult<Vec<f32>> {
+        // XGBoost's inplace prediction (XGBoosterPredictFromDense) only supports values and margins, so the row
+        // has to be copied into a DMatrix
+        let dmat = DMatrix::from_dense_with_missing(features, 1, missing)?;
+        self.check_feature_count(&dmat)?;
+        let iteration_range = self.default_iteration_range.unwrap_or((0, 0));
+        let (contribs, _shape) = self.predict_from_dmatrix(&dmat, PredictType::Contributions, iteration_range)?;
+        Ok(contribs)
     }
 
-    pub(crate) fn save_rabit_checkpoint(&self) -> XGBResult<()> {
-        xgb_call!(xgboost_sys::XGBoosterSaveRabitCheckpoint(self.handle))
+    /// Predict results directly from a dense row-major array of features, without building a `DMatrix`.
+    ///
+    /// `data` must contain `num_rows` rows of one value per feature in the model. Values equal to `missing`
+    /// (which may be NaN) are treated as missing, so batches using different missing value sentinels can be
+    /// scored without rebuilding them.
+    ///
+    /// As with [`predict`](struct.Booster.html#method.predict), uses the default iteration range if set, and
+    /// returns an array containing one entry per row (or row and output group, for multiclass models).
+    pub fn predict_dense(&self, data: &[f32], num_rows: usize, missing: f32) -> XGBResult<Vec<f32>> {
+        let iteration_range = self.default_iteration_range.unwrap_or((0, 0));
+        let (preds, _shape) = self.predict_from_dense(data, num_rows, missing, PredictType::Value, iteration_range)?;
+        Ok(preds)
     }
 
-    pub fn set_param(&mut self, name: &str, value: &str) -> XGBResult<()> {
-        let name = ffi::CString::new(name).unwrap();
-        let value = ffi::CString::new(value).unwrap();
-        xgb_call!(xgboost_sys::XGBoosterSetParam(self.handle, name.as_ptr(), value.as_ptr()))
+    /// Lazily predict results for a stream of dense rows (e.g. rows arriving from a message queue), one row at
+    /// a time, without buffering the stream or building a `DMatrix`.
+    ///
+    /// Each row must contain one value per feature in the model, with values equal to `missing` (which may be
+    /// NaN) treated as missing. As with [`predict_dense`](struct.Booster.html#method.predict_dense), each
+    /// item holds one entry (or one per output group, for multiclass models).
+    pub fn predict_stream<'a, I>(&'a self, rows: I, missing: f32) -> impl Iterator<Item = XGBResult<Vec<f32>>> + 'a
+        where I: Iterator<Item = Vec<f32>> + 'a
+    {
+        rows.map(move |row| self.predict_dense(&row, 1, missing))
     }
 
-    fn parse_eval_string(eval: &str, evnames: &[&str]) -> IndexMap<String, IndexMap<String, f32>> {
-        let mut result: IndexMap<String, IndexMap<String, f32>> = IndexMap::new();
-
-        debug!("Parsing evaluation line: {}", &eval);
-        for part in eval.split('\t').skip(1) {
-            for evname in evnames {
-                if part.starts_with(evname) {
-                    let metric_parts: Vec<&str> = part[evname.len()+1..].split(':').into_iter().collect();
-                    assert_eq!(metric_parts.len(), 2);
-                    let metric = metric_parts[0];
-                    let score = metric_parts[1].parse::<f32>()
-                        .unwrap_or_else(|_| panic!("Unable to parse XGBoost metrics output: {}", eval));
-
-                    let metric_map = result.entry(evname.to_string()).or_insert_with(IndexMap::new);
-                    metric_map.insert(metric.to_owned(), score);
-                }
-            }
+    /// Predict directly from a dense row-major array using `XGBoosterPredictFromDense`, without
+    /// constructing a `DMatrix`.
+    ///
+    /// `iteration_range` selects the boosting rounds used as `begin..end`, with `(0, 0)` meaning all rounds.
+    fn predict_from_dense(&self, data: &[f32], num_rows: usize, missing: f32, predict_type: PredictType,
+                          iteration_range: (u32, u32)) -> XGBResult<(Vec<f32>, Vec<usize>)> {
+        let num_cols = data.len().checked_div(num_rows).unwrap_or(0);
+        if num_rows == 0 || num_rows * num_cols != data.len() {
+            let msg = format!("Unable to split {} values into {} rows", data.len(), num_rows);
+            return Err(XGBError::new(msg));
         }
+        let values = ffi::CString::new(array_interface::describe_array(data, &[num_rows, num_cols], "<f4")).unwrap();
 
-        debug!("result: {:?}", &result);
-        result
+        // XGBoost's JSON parser follows Python's spelling of non-finite values
+        let missing = if missing.is_nan() {
+            "NaN".to_owned()
+        } else if missing.is_infinite() {
+            if missing > 0.0 { "Infinity".to_owned() } else { "-Infinity".to_owned() }
+        } else {
+            missing.to_string()
+        };
+        let config = format!("{{\"type\": {}, \"training\": false, \"iteration_begin\": {}, \
+                              \"iteration_end\": {}, \"strict_shape\": true, \"cache_id\": 0, \
+                              \"missing\": {}}}",
+                             predict_type as i32, iteration_range.0, iteration_range.1, missing);
+        let config = ffi::CString::new(config).unwrap();
+        let mut out_shape = ptr::null();
+        let mut out_dim = 0;
+        let mut out_result = ptr::null();
+        xgb_call!(xgboost_sys::XGBoosterPredictFromDense(self.handle,
+                                                         values.as_ptr(),
+                                                         config.as_ptr(),
+                                                         ptr::null_mut(),
+                                                         &mut out_shape,
+                                                         &mut out_dim,
+                                                         &mut out_result))?;
+        Booster::copy_prediction(out_shape, out_dim, out_result)
     }
 
-}
+    /// Predict results for given data, split into one vector of scores per query group.
+    ///
+    /// Intended for ranking, where `dmat` has groups set (e.g. with
+    /// [`set_group_sizes`](struct.DMatrix.html#method.set_group_sizes)). Returns an error if it doesn't.
+    pub fn predict_grouped(&self, dmat: &DMatrix) -> XGBResult<Vec<Vec<f32>>> {
+        let group_ptr = dmat.get_group()?;
+        if group_ptr.len() < 2 {
+            return Err(XGBError::new("Cannot group predictions for a DMatrix without group information"));
+        }
 
-impl Drop for Booster {
-    fn drop(&mut self) {
-        xgb_call!(xgboost_sys::XGBoosterFree(self.handle)).unwrap();
+        let preds = self.predict(dmat)?;
+        let num_rows = *group_ptr.last().unwrap() as usize;
+        if preds.len() != num_rows {
+            return Err(XGBError::DimensionMismatch { expected: num_rows, got: preds.len() });
+        }
+        Ok(group_ptr.windows(2)
+            .map(|w| preds[w[0] as usize..w[1] as usize].to_vec())
+            .collect())
     }
-}
-
-/// Maps a feature index to a name and type, used when dumping models as text.
-///
-/// See [dump_model](struct.Booster.html#method.dump_model) for usage.
-pub struct FeatureMap(BTreeMap<u32, (String, FeatureType)>);
 
-impl FeatureMap {
-    /// Read a `FeatureMap` from a file at given path.
-    ///
-    /// File should contain one feature definition per line, and be of the form:
-    /// ```text
-    /// <number>\t<name>\t<type>\n
-    /// ```
-    ///
-    /// Type should be one of:
-    /// * `i` - binary feature
-    /// * `q` - quantitative feature
-    /// * `int` - integer features
+    /// Predict results for given data, pairing each row's predictions with its external id.
     ///
-    /// E.g.:
-    /// ```text
-    /// 0   age int
-    /// 1   is-parent?=yes  i
-    /// 2   is-parent?=no   i
-    /// 3   income  int
-    /// ```
-    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<FeatureMap> {
-        let file = File::open(path)?;
-        let mut features: FeatureMap = FeatureMap(BTreeMap::new());
+    /// `ids` must have one entry per row of `dmat`, in row order. Each row gets one prediction, or several for
+    /// models with multiple outputs (e.g. multiclass probabilities).
+    pub fn predict_with_ids(&self, dmat: &DMatrix, ids: &[u64]) -> XGBResult<Vec<(u64, Vec<f32>)>> {
+        if ids.len() != dmat.num_rows() {
+            return Err(XGBError::DimensionMismatch { expected: dmat.num_rows(), got: ids.len() });
+        }
+        let (preds, (_num_rows, num_cols)) = self.predict_2d(dmat)?;
+        Ok(ids.iter()
+           .cloned()
+           .zip(preds.chunks(num_cols.max(1)).map(|row| row.to_vec()))
+           .collect())
+    }
 
-        for (i, line) in BufReader::new(&file).lines().enumerate() {
-            let line = line?;
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() != 3 {
-                let msg = format!("Unable to parse features from line {}, expected 3 tab separated values", i+1);
-                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+    /// Predict results for given data, and write them to a CSV file at given path.
+    ///
+    /// Writes one line per row, containing a single prediction, or comma separated predictions for models
+    /// with several outputs (e.g. multiclass probabilities). If `include_index` is set, each line starts
+    /// with the row's index. No header line is written.
+    pub fn predict_to_csv<P: AsRef<Path>>(&self, dmat: &DMatrix, path: P, include_index: bool) -> XGBResult<()> {
+        let (preds, shape) = self.predict_with_shape(dmat)?;
+        let num_cols: usize = shape.iter().skip(1).product();
+
+        let write_csv = || -> io::Result<()> {
+            let mut writer = BufWriter::new(File::create(path.as_ref())?);
+            for (i, row) in preds.chunks(num_cols.max(1)).enumerate() {
+                if include_index {
+                    write!(writer, "{},", i)?;
+                }
+                let values: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+                writeln!(writer, "{}", values.join(","))?;
             }
+            writer.flush()
+        };
+        write_csv().map_err(|err| XGBError::new(err.to_string()))
+    }
 
-            assert_eq!(parts.len(), 3);
-            let feature_num: u32 = match parts[0].parse() {
-                Ok(num)  => num,
-                Err(err) => {
-                    let msg = format!("Unable to parse features from line {}, could not parse feature number: {}",
-                                      i+1, err);
-                    return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
-                }
-            };
+    /// Compute feature contributions (SHAP values) for given data, and write them to a CSV file at given path.
+    ///
+    /// Rows are processed in chunks of at most `chunk_rows`, with each chunk's contributions appended to the file
+    /// before the next is computed, so only one chunk's contributions are held in memory at a time. Writes one
+    /// line per row with the same columns as
+    /// [`predict_contributions`](struct.Booster.html#method.predict_contributions) (i.e. one per feature, then
+    /// the bias term). No header line is written.
+    pub fn write_contribs<P: AsRef<Path>>(&self, dmat: &DMatrix, path: P, chunk_rows: usize) -> XGBResult<()> {
+        if chunk_rows == 0 {
+            return Err(XGBError::InvalidParameter("chunk_rows must be greater than 0".to_owned()));
+        }
 
-            let feature_name = &parts[1];
-            let feature_type = match FeatureType::from_str(&parts[2]) {
-                Ok(feature_type) => feature_type,
-                Err(msg)         => {
-                    let msg = format!("Unable to parse features from line {}: {}", i+1, msg);
-                    return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        let mut writer = BufWriter::new(File::create(path.as_ref()).map_err(|err| XGBError::new(err.to_string()))?);
+        let indices: Vec<usize> = (0..dmat.num_rows()).collect();
+        for chunk_indices in indices.chunks(chunk_rows) {
+            let chunk = dmat.slice(chunk_indices)?;
+            let (contribs, (_num_rows, num_cols)) = self.predict_contributions(&chunk)?;
+
+            let write_chunk = |writer: &mut BufWriter<File>| -> io::Result<()> {
+                for row in contribs.chunks(num_cols.max(1)) {
+                    let values: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+                    writeln!(writer, "{}", values.join(","))?;
                 }
+                Ok(())
             };
-            features.0.insert(feature_num, (feature_name.to_string(), feature_type));
+            write_chunk(&mut writer).map_err(|err| XGBError::new(err.to_string()))?;
         }
-        Ok(features)
+        writer.flush().map_err(|err| XGBError::new(err.to_string()))
     }
-}
-
-/// Indicates the type of a feature, used when dumping models as text.
-pub enum FeatureType {
-    /// Binary indicator feature.
-    Binary,
-
-    /// Quantitative feature (e.g. age, time, etc.), can be missing.
-    Quantitative,
 
-    /// Integer feature (when hinted, decision boundary will be integer).
-    Integer,
-}
+    /// Predict using `XGBoosterPredictFromDMatrix` with strict output shapes.
+    ///
+    /// `iteration_range` selects the boosting rounds used as `begin..end`, with `(0, 0)` meaning all rounds.
+    /// Callers are expected to have checked `dmat` with `check_feature_count` first.
+    fn predict_from_dmatrix(&self, dmat: &DMatrix, predict_type: PredictType, iteration_range: (u32, u32))
+                            -> XGBResult<(Vec<f32>, Vec<usize>)> {
+        Booster::predict_from_handles(self.handle, dmat.handle, predict_type, iteration_range)
+    }
 
-impl FromStr for FeatureType {
-    type Err = String;
+    /// Predict as with `predict_from_dmatrix`, given raw handles (which must be valid for the duration of the
+    /// call).
+    fn predict_from_handles(handle: xgboost_sys::BoosterHandle, dmat_handle: xgboost_sys::DMatrixHandle,
+                            predict_type: PredictType, iteration_range: (u32, u32))
+                            -> XGBResult<(Vec<f32>, Vec<usize>)> {
+        let config = format!("{{\"type\": {}, \"training\": false, \"iteration_begin\": {}, \
+                              \"iteration_end\": {}, \"strict_shape\": true}}",
+                             predict_type as i32, iteration_range.0, iteration_range.1);
+        let config = ffi::CString::new(config).unwrap();
+        let mut out_shape = ptr::null();
+        let mut out_dim = 0;
+        let mut out_result = ptr::null();
+        xgb_call!(xgboost_sys::XGBoosterPredictFromDMatrix(handle,
+                                                           dmat_handle,
+                                                           config.as_ptr(),
+                                                           &mut out_shape,
+                                                           &mut out_dim,
+                                                           &mut out_result))?;
+        Booster::copy_prediction(out_shape, out_dim, out_result)
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "i"   => Ok(FeatureType::Binary),
-            "q"   => Ok(FeatureType::Quantitative),
-            "int" => Ok(FeatureType::Integer),
-            _     => Err(format!("unrecognised feature type '{}', must be one of: 'i', 'q', 'int'", s))
+    /// Check `dmat` doesn't have more columns than this model has features, which XGBoost only rejects with
+    /// an unhelpful error message.
+    fn check_feature_count(&self, dmat: &DMatrix) -> XGBResult<()> {
+        let num_features = self.num_features()?;
+        // models which haven't been trained yet have no features
+        if num_features > 0 && dmat.num_cols() > num_features {
+            return Err(XGBError::FeatureCountMismatch { model: num_features, data: dmat.num_cols() });
         }
+        Ok(())
     }
-}
 
-impl fmt::Display for FeatureType {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let s = match self {
-            FeatureType::Binary => "i",
-            FeatureType::Quantitative => "q",
-            FeatureType::Integer => "int",
+    /// Copy the output of one of XGBoost's prediction functions which report their own output shape.
+    fn copy_prediction(out_shape: *const xgboost_sys::bst_ulong, out_dim: xgboost_sys::bst_ulong,
+                       out_result: *const f32) -> XGBResult<(Vec<f32>, Vec<usize>)> {
+        assert!(!out_shape.is_null());
+
+        let shape: Vec<usize> = unsafe { slice::from_raw_parts(out_shape, out_dim as usize) }
+            .iter()
+            .map(|dim| *dim as usize)
+            .collect();
+        let len = shape.iter().product();
+        let data = if len == 0 {
+            Vec::new()
+        } else {
+            assert!(!out_result.is_null());
+            unsafe { slice::from_raw_parts(out_result, len).to_vec() }
         };
-        write!(f, "{}", s)
+        Ok((data, shape))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use parameters::{self, learning, tree};
+    /// Predict margin for given data.
+    ///
+    /// Uses the same boosting rounds as [`predict`](struct.Booster.html#method.predict). Returns an array
+    /// containing one entry per row in the given data.
+    pub fn predict_margin(&self, dmat: &DMatrix) -> XGBResult<Vec<f32>> {
+        self.check_feature_count(dmat)?;
+        let iteration_range = self.default_iteration_range.unwrap_or((0, 0));
+        let (data, _shape) = self.predict_from_dmatrix(dmat, PredictType::Margin, iteration_range)?;
+        Ok(data)
+    }
 
-    fn read_train_matrix() -> XGBResult<DMatrix> {
-        DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train")
+    /// Predict both the raw margin and the transformed prediction (e.g. probability) for given data, from a single
+    /// prediction pass over the data.
+    ///
+    /// Only the margin is predicted by XGBoost, with the objective's link function (e.g. sigmoid for
+    /// `binary:logistic`) applied in Rust, so results match [`predict`](struct.Booster.html#method.predict) up to
+    /// floating point rounding. Uses the same boosting rounds as `predict`. Returns an error for objectives whose
+    /// transformation isn't an elementwise link function (e.g. `multi:softprob`).
+    ///
+    /// Besides the prediction itself, this makes two cheap C calls which don't depend on the size of `dmat`: one
+    /// to read the objective from the model's config (as with
+    /// [`save_config`](struct.Booster.html#method.save_config)), and one to check the number of features.
+    ///
+    /// Returns a tuple of (margins, predictions).
+    pub fn predict_margin_and_proba(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, Vec<f32>)> {
+        let config = self.config_json()?;
+        let objective = config["learner"]["objective"]["name"].as_str().unwrap_or("");
+        let link = Link::from_objective(objective)
+            .ok_or_else(|| XGBError::InvalidParameter(
+                format!("predict_margin_and_proba doesn't support objective {}", objective)))?;
+
+        let margin = self.predict_margin(dmat)?;
+        let proba = margin.iter().map(|m| link.apply(*m)).collect();
+        Ok((margin, proba))
     }
 
-    fn load_test_booster() -> Booster {
-        let dmat = read_train_matrix().expect("Reading train matrix failed");
-        Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat]).expect("Creating Booster failed")
+    /// Predict the contribution of this model's trees alone to the margin of given data, i.e. the margin from
+    /// [`predict_margin`](struct.Booster.html#method.predict_margin) minus the base margin it started from.
+    ///
+    /// Useful when stacking models, e.g. to inspect what the trees add on top of another model's outputs set
+    /// with [`DMatrix::set_base_margin`](struct.DMatrix.html#method.set_base_margin). If `dmat` has no base
+    /// margin set, the model's global bias (from `base_score`) is subtracted instead, which is only supported for
+    /// single output objectives.
+    pub fn predict_trees_only(&self, dmat: &DMatrix) -> XGBResult<Vec<f32>> {
+        let margin = self.predict_margin(dmat)?;
+        let base_margin = dmat.get_base_margin()?;
+        if !base_margin.is_empty() {
+            if base_margin.len() != margin.len() {
+                return Err(XGBError::DimensionMismatch { expected: margin.len(), got: base_margin.len() });
+            }
+            return Ok(margin.iter().zip(base_margin).map(|(m, base)| m - base).collect());
+        }
+
+        let config = self.config_json()?;
+        let objective = config["learner"]["objective"]["name"].as_str().unwrap_or("");
+        let link = match Link::from_objective(objective) {
+            Some(link) if self.output_dim()? == 1 => link,
+            _ => return Err(XGBError::InvalidParameter(
+                format!("predict_trees_only requires a base_margin for objective {}", objective))),
+        };
+        let bias = link.inverse(self.base_score()?);
+        Ok(margin.iter().map(|m| m - bias).collect())
     }
 
-    #[test]
-    fn set_booster_param() {
-        let mut booster = load_test_booster();
-        let res = booster.set_param("key", "value");
-        assert!(res.is_ok());
+    /// Get predicted leaf index for each sample in given data.
+    ///
+    /// Uses the same boosting rounds as [`predict`](struct.Booster.html#method.predict).
+    ///
+    /// Returns an array of shape (number of samples, number of trees) as tuple of (data, num_rows).
+    ///
+    /// Note: the leaf index of a tree is unique per tree, so e.g. leaf 1 could be found in both tree 1 and tree 0.
+    pub fn predict_leaf(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize))> {
+        self.check_feature_count(dmat)?;
+        let iteration_range = self.default_iteration_range.unwrap_or((0, 0));
+        let (data, _shape) = self.predict_from_dmatrix(dmat, PredictType::Leaf, iteration_range)?;
+        let num_rows = dmat.num_rows();
+        let num_cols = data.len() / num_rows;
+        Ok((data, (num_rows, num_cols)))
     }
 
-    #[test]
-    fn load_rabit_version() {
-        let version = load_test_booster().load_rabit_checkpoint().unwrap();
-        assert_eq!(version, 0);
+    /// Get feature contributions (SHAP values) for each prediction.
+    ///
+    /// Uses the same boosting rounds as [`predict`](struct.Booster.html#method.predict).
+    ///
+    /// The sum of all feature contributions is equal to the run untransformed margin value of the
+    /// prediction.
+    ///
+    /// Returns an array of shape (number of samples, number of features + 1) as a tuple of
+    /// (data, num_rows). The final column contains the bias term.
+    pub fn predict_contributions(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize))> {
+        self.check_feature_count(dmat)?;
+        let iteration_range = self.default_iteration_range.unwrap_or((0, 0));
+        let (data, _shape) = self.predict_from_dmatrix(dmat, PredictType::Contributions, iteration_range)?;
+        let num_rows = dmat.num_rows();
+        let num_cols = data.len() / num_rows;
+        Ok((data, (num_rows, num_cols)))
     }
 
-    #[test]
-    fn get_set_attr() {
-        let mut booster = load_test_booster();
-        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
-        assert_eq!(attr, None);
+    /// Get the bias term of this model's feature contributions (SHAP values), i.e. the expected margin
+    /// before any feature is known.
+    ///
+    /// This is the same for every row, and equal to the final column of
+    /// [`predict_contributions`](struct.Booster.html#method.predict_contributions) (using the same boosting rounds).
+    /// Only supported for single output models.
+    pub fn shap_base_value(&self) -> XGBResult<f32> {
+        let output_dim = self.output_dim()?;
+        if output_dim != 1 {
+            return Err(XGBError::InvalidParameter(
+                format!("shap_base_value requires a single output model, found {} outputs", output_dim)));
+        }
 
-        booster.set_attribute("foo", "bar").expect("Setting attribute failed");
-        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
-        assert_eq!(attr, Some("bar".to_owned()));
+        // the bias term doesn't depend on the row, so explain a row with every feature missing
+        let features = vec![f32::NAN; self.num_features()?];
+        let contribs = self.explain_row(&features, f32::NAN)?;
+        contribs.last()
+            .cloned()
+            .ok_or_else(|| XGBError::new("XGBoost returned no feature contributions"))
     }
 
-    #[test]
-    fn save_and_load_from_buffer() {
-        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
-        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat_train]).unwrap();
-        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
-        assert_eq!(attr, None);
+    /// Get SHAP interaction values for each pair of features for each prediction.
+    ///
+    /// Uses the same boosting rounds as [`predict`](struct.Booster.html#method.predict).
+    ///
+    /// The sum of each row (or column) of the interaction values equals the corresponding SHAP
+    /// value (from `predict_contributions`), and the sum of the entire matrix equals the raw
+    /// untransformed margin value of the prediction.
+    ///
+    /// Returns an array of shape (number of samples, number of features + 1, number of features + 1).
+    /// The final row and column contain the bias terms.
+    pub fn predict_interactions(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize, usize))> {
+        self.check_feature_count(dmat)?;
+        let iteration_range = self.default_iteration_range.unwrap_or((0, 0));
+        let (data, _shape) = self.predict_from_dmatrix(dmat, PredictType::Interactions, iteration_range)?;
+        let num_rows = dmat.num_rows();
 
-        booster.set_attribute("foo", "bar").expect("Setting attribute failed");
-        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
-        assert_eq!(attr, Some("bar".to_owned()));
+        let dim = ((data.len() / num_rows) as f64).sqrt() as usize;
+        Ok((data, (num_rows, dim, dim)))
+    }
 
-        let dir = tempfile::tempdir().expect("create temp dir");
-        let path = dir.path().join("test-xgboost-model");
+    /// Get a dump of this model as a string.
+    ///
+    /// * `with_statistics` - whether to include statistics in output dump
+    /// * `feature_map` - if given, map feature IDs to feature names from given map
+    pub fn dump_model(&self, with_statistics: bool, feature_map: Option<&FeatureMap>) -> XGBResult<String> {
+        if let Some(fmap) = feature_map {
+            let tmp_dir = match tempfile::tempdir() {
+                Ok(dir) => dir,
+                Err(err) => return Err(XGBError::new(err.to_string())),
+            };
+
+            let file_path = tmp_dir.path().join("fmap.txt");
+            let mut file: File = match File::create(&file_path) {
+                Ok(f) => f,
+                Err(err) => return Err(XGBError::new(err.to_string())),
+            };
+
+            for (feature_num, (feature_name, feature_type)) in &fmap.0 {
+                writeln!(file, "{}\t{}\t{}", feature_num, feature_name, feature_type).unwrap();
+            }
+
+            Ok(self.dump_model_fmap(with_statistics, Some(&file_path), "text")?.join("\n"))
+        } else {
+            Ok(self.dump_model_fmap(with_statistics, None, "text")?.join("\n"))
+        }
+    }
+
+    /// Get a dump of this model in JSON format, with one JSON document per tree.
+    ///
+    /// * `with_statistics` - whether to include statistics (gain/cover) in output dump
+    pub fn dump_model_json(&self, with_statistics: bool) -> XGBResult<Vec<String>> {
+        self.dump_model_fmap(with_statistics, None, "json")
+    }
+
+    /// Get a dump of this model in JSON format as with [`dump_model_json`](struct.Booster.html#method.dump_model_json),
+    /// with leaf values and split thresholds rounded to `precision` decimal places (if given).
+    ///
+    /// Makes dumps smaller and easier to diff, at the cost of no longer exactly reproducing the model's
+    /// predictions. Statistics (gain/cover) are left as they are.
+    pub fn dump_model_json_with_precision(&self, with_statistics: bool, precision: Option<usize>)
+                                          -> XGBResult<Vec<String>> {
+        let dumps = self.dump_model_json(with_statistics)?;
+        let precision = match precision {
+            Some(precision) => precision,
+            None            => return Ok(dumps),
+        };
+
+        let scale = 10f64.powi(precision as i32);
+        dumps.iter()
+            .map(|dump| {
+                let mut tree: serde_json::Value = serde_json::from_str(dump)
+                    .map_err(|err| XGBError::new(format!("Unable to parse tree dump: {}", err)))?;
+                Booster::round_tree_values(&mut tree, scale);
+                Ok(tree.to_string())
+            })
+            .collect()
+    }
+
+    /// Round the leaf values and split thresholds of a JSON tree dump node (and its descendants) to a multiple
+    /// of `1 / scale`.
+    fn round_tree_values(node: &mut serde_json::Value, scale: f64) {
+        for key in &["leaf", "split_condition"] {
+            let rounded = node[*key].as_f64().map(|value| (value * scale).round() / scale);
+            if let Some(value) = rounded.and_then(serde_json::Number::from_f64) {
+                node[*key] = serde_json::Value::Number(value);
+            }
+        }
+        if let Some(children) = node.get_mut("children").and_then(|c| c.as_array_mut()) {
+            for child in children {
+                Booster::round_tree_values(child, scale);
+            }
+        }
+    }
+
+    /// Lazily dump this model in JSON format, one tree at a time.
+    ///
+    /// Produces the same trees as [`dump_model_json`](struct.Booster.html#method.dump_model_json), but only
+    /// dumps a single boosting round from XGBoost at a time, so large models can be streamed (e.g. to disk)
+    /// without holding every tree in memory at once.
+    pub fn dump_trees<'a>(&'a self, with_statistics: bool) -> impl Iterator<Item = XGBResult<String>> + 'a {
+        TreeDumps {
+            booster: self,
+            with_statistics,
+            next_round: 0,
+            num_rounds: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Get the sorted indices of all features used in at least one split of this model.
+    ///
+    /// Features which are never split on (e.g. due to column sampling, or because they're constant) can be
+    /// dropped from the input data without changing predictions.
+    pub fn used_features(&self) -> XGBResult<Vec<usize>> {
+        let mut features = BTreeSet::new();
+        for tree in self.dump_model_json(false)? {
+            let tree: serde_json::Value = serde_json::from_str(&tree)
+                .map_err(|err| XGBError::new(format!("Unable to parse tree dump: {}", err)))?;
+            Booster::collect_split_features(&tree, &mut features)?;
+        }
+        Ok(features.into_iter().collect())
+    }
+
+    fn collect_split_features(node: &serde_json::Value, features: &mut BTreeSet<usize>) -> XGBResult<()> {
+        let split = match node.get("split") {
+            Some(split) => split,
+            None        => return Ok(()),
+        };
+
+        // without a feature map, splits are named by index, optionally prefixed by 'f'
+        let index = match *split {
+            serde_json::Value::Number(ref n) => n.as_u64().map(|n| n as usize),
+            serde_json::Value::String(ref s) => s.trim_start_matches('f').parse::<usize>().ok(),
+            _                                => None,
+        };
+        match index {
+            Some(index) => { features.insert(index); },
+            None        => return Err(XGBError::new(format!("Unable to parse split feature: {}", split))),
+        }
+
+        if let Some(children) = node.get("children").and_then(|c| c.as_array()) {
+            for child in children {
+                Booster::collect_split_features(child, features)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the learned per-feature weights and bias term of a linear (`gblinear`) model.
+    ///
+    /// Margin predictions are `base_score + bias + weights·x`, with missing features contributing nothing.
+    ///
+    /// Returns an error if this booster isn't linear, or if it has more than one output group (e.g. multiclass).
+    pub fn linear_weights(&self) -> XGBResult<(Vec<f32>, f32)> {
+        let config = self.config_json()?;
+        let booster_name = &config["learner"]["gradient_booster"]["name"];
+        if booster_name.as_str() != Some("gblinear") {
+            return Err(XGBError::InvalidParameter(
+                format!("linear_weights requires a gblinear booster, found {}", booster_name)));
+        }
+
+        let dump = self.dump_model_json(false)?;
+        let dump = match dump.first() {
+            Some(dump) => dump,
+            None       => return Err(XGBError::new("Empty linear model dump")),
+        };
+        let model: serde_json::Value = serde_json::from_str(dump)
+            .map_err(|err| XGBError::new(format!("Unable to parse linear model dump: {}", err)))?;
+        let floats = |name: &str| -> XGBResult<Vec<f32>> {
+            model[name].as_array()
+                .and_then(|values| values.iter().map(|v| v.as_f64().map(|v| v as f32)).collect())
+                .ok_or_else(|| XGBError::new(format!("Unable to parse '{}' from linear model dump", name)))
+        };
+
+        let bias = floats("bias")?;
+        if bias.len() != 1 {
+            return Err(XGBError::new(format!("Expected a single output group, found {}", bias.len())));
+        }
+        Ok((floats("weight")?, bias[0]))
+    }
+
+    /// Parse every tree of this model from its JSON dump, in the order they were boosted.
+    ///
+    /// Split features are resolved to their index, whether or not the model has named features.
+    pub fn trees(&self) -> XGBResult<Vec<Tree>> {
+        let feature_names = self.feature_names()?;
+        self.dump_model_json(true)?
+            .iter()
+            .map(|dump| Tree::from_json(dump, &feature_names))
+            .collect()
+    }
+
+    /// Get the depth of every tree in this model, in the order they were boosted.
+    ///
+    /// See [`Tree::depth`](struct.Tree.html#method.depth).
+    pub fn tree_depths(&self) -> XGBResult<Vec<u32>> {
+        Ok(self.trees()?.iter().map(|tree| tree.depth()).collect())
+    }
+
+    /// Convert this model into a [`Forest`](struct.Forest.html), which predicts entirely in Rust without
+    /// calling into XGBoost.
+    ///
+    /// Keeps only trees from the boosting rounds chosen by
+    /// [`set_default_iteration_range`](struct.Booster.html#method.set_default_iteration_range), if set.
+    ///
+    /// Only single output `gbtree` models are supported, so returns an error for e.g. `dart` or `gblinear`
+    /// boosters, and multiclass or multi-target models.
+    pub fn to_forest(&self) -> XGBResult<Forest> {
+        let config = self.config_json()?;
+        let booster_name = &config["learner"]["gradient_booster"]["name"];
+        if booster_name.as_str() != Some("gbtree") {
+            return Err(XGBError::InvalidParameter(
+                format!("to_forest requires a gbtree booster, found {}", booster_name)));
+        }
+        let objective = config["learner"]["objective"]["name"].as_str().unwrap_or("");
+        let link = match Link::from_objective(objective) {
+            Some(link) if self.output_dim()? == 1 => link,
+            _ => return Err(XGBError::InvalidParameter(format!("to_forest doesn't support objective {}", objective))),
+        };
+
+        let mut trees = self.trees()?;
+        let num_rounds = self.num_boosted_rounds()? as usize;
+        if let (Some((begin, end)), true) = (self.default_iteration_range, num_rounds > 0) {
+            let trees_per_round = trees.len() / num_rounds;
+            let end = if end == 0 { num_rounds } else { num_rounds.min(end as usize) };
+            let begin = begin as usize;
+            trees = trees.into_iter()
+                .skip(begin * trees_per_round)
+                .take(end.saturating_sub(begin) * trees_per_round)
+                .collect();
+        }
+        Ok(Forest::new(trees, self.base_score()?, link))
+    }
+
+    /// Get the number of trees in this model, counted from its dump.
+    ///
+    /// For `gbtree` models this is the number of boosting rounds times `num_parallel_tree` (times the number of
+    /// classes for multiclass models). For `dart` models, every tree built is counted, including those whose
+    /// weight was reduced by dropout. Linear models have no trees.
+    pub fn num_trees(&self) -> XGBResult<u32> {
+        let config = self.config_json()?;
+        if config["learner"]["gradient_booster"]["name"].as_str() == Some("gblinear") {
+            return Ok(0);
+        }
+        Ok(self.dump_model_json(false)?.len() as u32)
+    }
+
+    /// Combine several models into one averaging their predictions, by concatenating their trees with every
+    /// leaf value scaled by `1 / boosters.len()`.
+    ///
+    /// Useful for bagging, e.g. combining models trained on different samples of the data. The result's margin
+    /// is the average of the margins of the given models, so for objectives with a non-linear link (e.g.
+    /// `binary:logistic`) its predictions are the link of the averaged margins, not averaged probabilities.
+    ///
+    /// Every model must be a `gbtree` model (not `dart` or `gblinear`), with the same objective, number of
+    /// features, number of outputs and `base_score`, otherwise an `XGBError::InvalidParameter` error is returned.
+    /// The result keeps the attributes and feature names of the first model (apart from early stopping
+    /// results), and its boosting rounds are those of every model in turn.
+    pub fn concat_trees(boosters: &[&Booster]) -> XGBResult<Booster> {
+        let first = match boosters.first() {
+            Some(first) => first,
+            None        => return Err(XGBError::InvalidParameter("no boosters to concatenate".to_owned())),
+        };
+        let parse_model = |booster: &Booster| -> XGBResult<serde_json::Value> {
+            let bytes = booster.save_to_buffer("json")?;
+            serde_json::from_slice(&bytes).map_err(|err| XGBError::new(format!("Unable to parse model JSON: {}", err)))
+        };
+        let signature = |model: &serde_json::Value| {
+            let learner = &model["learner"];
+            (learner["gradient_booster"]["name"].clone(),
+             learner["objective"]["name"].clone(),
+             learner["learner_model_param"]["num_feature"].clone(),
+             learner["learner_model_param"]["num_class"].clone(),
+             learner["learner_model_param"]["num_target"].clone(),
+             learner["learner_model_param"]["base_score"].clone())
+        };
+
+        let mut merged = parse_model(first)?;
+        let expected = signature(&merged);
+        if expected.0.as_str() != Some("gbtree") {
+            return Err(XGBError::InvalidParameter(format!("concat_trees requires gbtree models, found {}", expected.0)));
+        }
+
+        let scale = 1.0 / boosters.len() as f64;
+        let mut trees = Vec::new();
+        let mut tree_info = Vec::new();
+        let mut iteration_indptr = vec![serde_json::Value::from(0)];
+        for booster in boosters {
+            let model = parse_model(booster)?;
+            if signature(&model) != expected {
+                return Err(XGBError::InvalidParameter(
+                    "concat_trees requires models with the same booster, objective, number of features, \
+                     number of outputs and base_score".to_owned()));
+            }
+
+            let gbtree = &model["learner"]["gradient_booster"]["model"];
+            let offset = trees.len() as u64;
+            if let Some(indptr) = gbtree["iteration_indptr"].as_array() {
+                for end in indptr.iter().skip(1) {
+                    iteration_indptr.push(serde_json::Value::from(offset + end.as_u64().unwrap_or(0)));
+                }
+            }
+            if let Some(info) = gbtree["tree_info"].as_array() {
+                tree_info.extend(info.iter().cloned());
+            }
+            for tree in gbtree["trees"].as_array().cloned().unwrap_or_default() {
+                trees.push(Booster::scale_json_tree(tree, scale, trees.len()));
+            }
+        }
+
+        {
+            let gbtree = &mut merged["learner"]["gradient_booster"]["model"];
+            gbtree["gbtree_model_param"]["num_trees"] = serde_json::Value::from(trees.len().to_string());
+            if gbtree.get("iteration_indptr").is_some() {
+                gbtree["iteration_indptr"] = serde_json::Value::from(iteration_indptr);
+            }
+            gbtree["tree_info"] = serde_json::Value::from(tree_info);
+            gbtree["trees"] = serde_json::Value::from(trees);
+        }
+        if let Some(attributes) = merged["learner"]["attributes"].as_object_mut() {
+            attributes.remove("best_iteration");
+            attributes.remove("best_score");
+        }
+
+        Booster::load_buffer(merged.to_string().as_bytes())
+    }
+
+    /// Scale every leaf value of a tree from a JSON model by `scale`, and renumber it as tree `id`.
+    fn scale_json_tree(mut tree: serde_json::Value, scale: f64, id: usize) -> serde_json::Value {
+        let leaves: Vec<bool> = tree["left_children"].as_array()
+            .map(|children| children.iter().map(|child| child.as_i64() == Some(-1)).collect())
+            .unwrap_or_default();
+        for key in &["split_conditions", "base_weights"] {
+            if let Some(values) = tree[*key].as_array_mut() {
+                for (value, is_leaf) in values.iter_mut().zip(&leaves) {
+                    if let (true, Some(v)) = (*is_leaf, value.as_f64()) {
+                        *value = serde_json::Value::from(v * scale);
+                    }
+                }
+            }
+        }
+        tree["id"] = serde_json::Value::from(id);
+        tree
+    }
+
+    /// Get the number of boosting rounds this model has been trained for.
+    pub fn num_boosted_rounds(&self) -> XGBResult<u32> {
+        let mut out = 0;
+        xgb_call!(xgboost_sys::XGBoosterBoostedRounds(self.handle, &mut out))?;
+        Ok(out as u32)
+    }
+
+    /// Get the names of each feature this model was trained with, as set on its training matrix by
+    /// [`DMatrix::set_feature_names`](struct.DMatrix.html#method.set_feature_names).
+    ///
+    /// Returns an empty list if features aren't named.
+    pub fn feature_names(&self) -> XGBResult<Vec<String>> {
+        self.get_str_feature_info("feature_name")
+    }
+
+    /// Get the type of each feature this model was trained with, e.g. to check which features it treats as
+    /// categorical, as set on its training matrix by
+    /// [`DMatrix::set_feature_types`](struct.DMatrix.html#method.set_feature_types).
+    ///
+    /// Kept when saving in JSON or UBJSON formats. Returns an empty list if feature types weren't set.
+    pub fn feature_types(&self) -> XGBResult<Vec<FeatureType>> {
+        self.get_str_feature_info("feature_type")?
+            .iter()
+            .map(|t| t.parse::<FeatureType>().map_err(XGBError::InvalidData))
+            .collect()
+    }
+
+    fn get_str_feature_info(&self, field: &str) -> XGBResult<Vec<String>> {
+        let field = ffi::CString::new(field).unwrap();
+        let mut out_len = 0;
+        let mut out_features = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGBoosterGetStrFeatureInfo(self.handle,
+                                                          field.as_ptr(),
+                                                          &mut out_len,
+                                                          &mut out_features))?;
+        if out_len == 0 {
+            return Ok(Vec::new());
+        }
+        assert!(!out_features.is_null());
+        let out_ptrs = unsafe { slice::from_raw_parts(out_features, out_len as usize) };
+        Ok(out_ptrs.iter()
+           .map(|str_ptr| unsafe { ffi::CStr::from_ptr(*str_ptr).to_str().unwrap().to_owned() })
+           .collect())
+    }
+
+    fn set_str_feature_info(&mut self, field: &str, values: &[String]) -> XGBResult<()> {
+        let field = ffi::CString::new(field).unwrap();
+        let values: Vec<ffi::CString> = values.iter().map(|v| ffi::CString::new(v.as_str()).unwrap()).collect();
+        let mut ptrs: Vec<*const libc::c_char> = values.iter().map(|v| v.as_ptr()).collect();
+        xgb_call!(xgboost_sys::XGBoosterSetStrFeatureInfo(self.handle,
+                                                          field.as_ptr(),
+                                                          ptrs.as_mut_ptr(),
+                                                          ptrs.len() as xgboost_sys::bst_ulong))
+    }
+
+    /// Get the importance of each feature used by this model, according to given importance type.
+    ///
+    /// Returns a map of feature name to score. Features are named as on the training matrix if it had
+    /// feature names set, otherwise XGBoost's default `fN` names (where `N` is the feature index) are used.
+    /// Features which are never split on are omitted.
+    pub fn feature_importance(&self, importance_type: ImportanceType) -> XGBResult<HashMap<String, f32>> {
+        let config = format!("{{\"importance_type\": \"{}\", \"feature_map\": \"\"}}", importance_type);
+        let config = ffi::CString::new(config).unwrap();
+        let mut out_n_features = 0;
+        let mut out_features = ptr::null_mut();
+        let mut out_dim = 0;
+        let mut out_shape = ptr::null();
+        let mut out_scores = ptr::null();
+        xgb_call!(xgboost_sys::XGBoosterFeatureScore(self.handle,
+                                                     config.as_ptr(),
+                                                     &mut out_n_features,
+                                                     &mut out_features,
+                                                     &mut out_dim,
+                                                     &mut out_shape,
+                                                     &mut out_scores))?;
+
+        let num_features = out_n_features as usize;
+        if num_features == 0 {
+            return Ok(HashMap::new());
+        }
+        let (scores, _shape) = Booster::copy_prediction(out_shape, out_dim, out_scores)?;
+        // linear models have one score per feature per output group, so sum them up
+        let groups = scores.len() / num_features;
+        let names = unsafe { slice::from_raw_parts(out_features, num_features) };
+        Ok(names.iter()
+           .enumerate()
+           .map(|(i, name)| {
+               let name = unsafe { ffi::CStr::from_ptr(*name).to_str().unwrap().to_owned() };
+               (name, scores[i * groups..(i + 1) * groups].iter().sum())
+           })
+           .collect())
+    }
+
+    /// Get split statistics for every feature used by this model, sorted by feature index.
+    ///
+    /// Gives the same scores as calling [`feature_importance`](struct.Booster.html#method.feature_importance)
+    /// with each [`ImportanceType`](enum.ImportanceType.html), but computed from a single pass over the
+    /// model's [`trees`](struct.Booster.html#method.trees). Features which are never split on are omitted.
+    pub fn feature_report(&self) -> XGBResult<Vec<FeatureStat>> {
+        // (number of splits, total gain, total cover) per feature index
+        let mut totals: BTreeMap<usize, (u32, f32, f32)> = BTreeMap::new();
+        for tree in self.trees()? {
+            for node in tree.nodes() {
+                if let Node::Split { feature, gain, cover, .. } = *node {
+                    let entry = totals.entry(feature).or_insert((0, 0.0, 0.0));
+                    entry.0 += 1;
+                    entry.1 += gain;
+                    entry.2 += cover;
+                }
+            }
+        }
+
+        let feature_names = self.feature_names()?;
+        Ok(totals.into_iter()
+           .map(|(feature, (splits, total_gain, total_cover))| FeatureStat {
+               name: feature_names.get(feature).cloned().unwrap_or_else(|| format!("f{}", feature)),
+               weight: splits as f32,
+               gain: total_gain / splits as f32,
+               cover: total_cover / splits as f32,
+               total_gain,
+               total_cover,
+           })
+           .collect())
+    }
+
+    /// Get the tree construction algorithm (e.g. `exact`, `approx` or `hist`) used to train this model.
+    ///
+    /// If the `tree_method` parameter was `auto`, this is the method XGBoost chose in its place, which is
+    /// only known once at least one boosting round has been trained. Returns `None` for linear models.
+    pub fn tree_method(&self) -> XGBResult<Option<String>> {
+        Ok(self.tree_methods()?.map(|(_, resolved)| resolved))
+    }
+
+    /// Get the configured `tree_method` parameter of a tree model, along with the method actually used
+    /// (found from XGBoost's choice of tree updater).
+    fn tree_methods(&self) -> XGBResult<Option<(String, String)>> {
+        let config = self.config_json()?;
+        let mut gbtree = &config["learner"]["gradient_booster"];
+        if gbtree["name"].as_str() == Some("dart") {
+            gbtree = &gbtree["gbtree"];
+        }
+        if gbtree["name"].as_str() != Some("gbtree") {
+            return Ok(None);
+        }
+
+        let configured = gbtree["gbtree_train_param"]["tree_method"].as_str().unwrap_or("auto").to_owned();
+        if configured != "auto" {
+            return Ok(Some((configured.clone(), configured)));
+        }
+        // updaters are either a list of configs or a map keyed by name, depending on XGBoost's version
+        let updaters: Vec<&str> = match gbtree["updater"] {
+            serde_json::Value::Array(ref updaters) => updaters.iter().filter_map(|u| u["name"].as_str()).collect(),
+            serde_json::Value::Object(ref updaters) => updaters.keys().map(|name| name.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        let resolved = updaters.iter()
+            .filter_map(|updater| match *updater {
+                "grow_colmaker"                      => Some("exact"),
+                "grow_histmaker" | "grow_gpu_approx" => Some("approx"),
+                "grow_quantile_histmaker"            => Some("hist"),
+                "grow_gpu_hist"                      => Some("gpu_hist"),
+                _                                    => None,
+            })
+            .next()
+            .unwrap_or("auto");
+        Ok(Some((configured, resolved.to_owned())))
+    }
+
+    /// Get the number of features this model was trained with.
+    pub fn num_features(&self) -> XGBResult<usize> {
+        let mut out = 0;
+        xgb_call!(xgboost_sys::XGBoosterGetNumFeature(self.handle, &mut out))?;
+        Ok(out as usize)
+    }
+
+    /// Get a new Booster containing only the trees from boosting rounds `begin..end` of this model.
+    fn slice_rounds(&self, begin: u32, end: u32) -> XGBResult<Booster> {
+        let mut handle = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGBoosterSlice(self.handle, begin as i32, end as i32, 1, &mut handle))?;
+        Ok(Booster::from_handle(handle))
+    }
+
+    fn dump_model_fmap(&self, with_statistics: bool, feature_map_path: Option<&PathBuf>, format: &str)
+                       -> XGBResult<Vec<String>> {
+        let fmap = if let Some(path) = feature_map_path {
+            ffi::CString::new(path.as_os_str().as_bytes()).unwrap()
+        } else {
+            ffi::CString::new("").unwrap()
+        };
+        let format = ffi::CString::new(format).unwrap();
+        let mut out_len = 0;
+        let mut out_dump_array = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGBoosterDumpModelEx(self.handle,
+                                                    fmap.as_ptr(),
+                                                    with_statistics as i32,
+                                                    format.as_ptr(),
+                                                    &mut out_len,
+                                                    &mut out_dump_array))?;
+
+        let out_ptr_slice = unsafe { slice::from_raw_parts(out_dump_array, out_len as usize) };
+        let out_vec: Vec<String> = out_ptr_slice.iter()
+            .map(|str_ptr| unsafe { ffi::CStr::from_ptr(*str_ptr).to_str().unwrap().to_owned() })
+            .collect();
+
+        assert_eq!(out_len as usize, out_vec.len());
+        Ok(out_vec)
+    }
+
+    pub(crate) fn load_rabit_checkpoint(&self) -> XGBResult<i32> {
+        let mut version = 0;
+        xgb_call!(xgboost_sys::XGBoosterLoadRabitCheckpoint(self.handle, &mut version))?;
+        Ok(version)
+    }
+
+    pub(crate) fn save_rabit_checkpoint(&self) -> XGBResult<()> {
+        xgb_call!(xgboost_sys::XGBoosterSaveRabitCheckpoint(self.handle))
+    }
+
+    /// Set the predictor algorithm used by this model for subsequent predictions.
+    ///
+    /// Useful for models loaded from disk, e.g. forcing `Predictor::Cpu` for a model that was trained
+    /// with a GPU predictor.
+    pub fn set_predictor(&mut self, predictor: Predictor) -> XGBResult<()> {
+        self.set_param("predictor", &predictor.to_string())
+    }
+
+    /// Limit this model to `nthread` threads for subsequent calls (e.g. predictions), returning it.
+    ///
+    /// Useful when serving concurrent requests, where each prediction using every core leads to OpenMP
+    /// threads contending with each other. Only affects this Booster: it overrides the `threads` parameter it
+    /// was created with, and takes precedence over XGBoost's process wide thread count. A value of 0 means
+    /// using as many threads as are available. Results are the same for any number of threads.
+    pub fn with_nthread(mut self, nthread: u32) -> XGBResult<Self> {
+        self.set_param("nthread", &nthread.to_string())?;
+        Ok(self)
+    }
+
+    /// Get the number of threads this model uses, as set by
+    /// [`with_nthread`](struct.Booster.html#method.with_nthread), with 0 meaning as many as are available.
+    fn nthread(&self) -> XGBResult<usize> {
+        let config = self.config_json()?;
+        let nthread = config["learner"]["generic_param"]["nthread"].as_str()
+            .and_then(|nthread| nthread.parse().ok())
+            .unwrap_or(0);
+        Ok(nthread)
+    }
+
+    pub fn set_param(&mut self, name: &str, value: &str) -> XGBResult<()> {
+        let name = ffi::CString::new(name).unwrap();
+        let value = ffi::CString::new(value).unwrap();
+        xgb_call!(xgboost_sys::XGBoosterSetParam(self.handle, name.as_ptr(), value.as_ptr()))
+    }
+
+    fn parse_eval_string(eval: &str, evnames: &[&str]) -> IndexMap<String, IndexMap<String, f32>> {
+        let mut result: IndexMap<String, IndexMap<String, f32>> = IndexMap::new();
+
+        debug!("Parsing evaluation line: {}", &eval);
+        for part in eval.split('\t').skip(1) {
+            // match the longest name, so e.g. 'train2-rmse' isn't attributed to a dataset named 'train'
+            let evname = evnames.iter()
+                .filter(|evname| part.starts_with(*evname) && part[evname.len()..].starts_with('-'))
+                .max_by_key(|evname| evname.len());
+            if let Some(evname) = evname {
+                // metric names may contain '-' or '@' (e.g. 'map@4-'), but scores never contain ':'
+                let mut metric_parts = part[evname.len()+1..].rsplitn(2, ':');
+                let score = metric_parts.next()
+                    .and_then(|score| score.parse::<f32>().ok())
+                    .unwrap_or_else(|| panic!("Unable to parse XGBoost metrics output: {}", eval));
+                let metric = metric_parts.next()
+                    .unwrap_or_else(|| panic!("Unable to parse XGBoost metrics output: {}", eval));
+
+                let metric_map = result.entry(evname.to_string()).or_insert_with(IndexMap::new);
+                metric_map.insert(metric.to_owned(), score);
+            }
+        }
+
+        debug!("result: {:?}", &result);
+        result
+    }
+
+}
+
+impl Drop for Booster {
+    fn drop(&mut self) {
+        xgb_call!(xgboost_sys::XGBoosterFree(self.handle)).unwrap();
+    }
+}
+
+/// Iterator over the JSON dumps of each tree in a Booster, see
+/// [`dump_trees`](struct.Booster.html#method.dump_trees).
+struct TreeDumps<'a> {
+    booster: &'a Booster,
+    with_statistics: bool,
+    next_round: u32,
+    num_rounds: Option<u32>,
+    pending: VecDeque<String>,
+}
+
+impl<'a> Iterator for TreeDumps<'a> {
+    type Item = XGBResult<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(tree) = self.pending.pop_front() {
+                return Some(Ok(tree));
+            }
+
+            let num_rounds = match self.num_rounds {
+                Some(n) => n,
+                None => match self.booster.num_boosted_rounds() {
+                    Ok(n) => {
+                        self.num_rounds = Some(n);
+                        n
+                    },
+                    Err(err) => {
+                        // don't retry after an error
+                        self.num_rounds = Some(0);
+                        return Some(Err(err));
+                    },
+                },
+            };
+
+            if self.next_round >= num_rounds {
+                return None;
+            }
+
+            // a single round can contain several trees (e.g. multiclass or boosted random forests)
+            let round = self.next_round;
+            self.next_round += 1;
+            let trees = self.booster.slice_rounds(round, round + 1)
+                .and_then(|bst| bst.dump_model_json(self.with_statistics));
+            match trees {
+                Ok(trees) => self.pending.extend(trees),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// Evaluation metrics known not to suit groups of learning objectives, checked before training.
+static INCOMPATIBLE_METRICS: &[(&[&str], &[&str])] = &[
+    // continuous targets, so classification and ranking metrics are meaningless
+    (&["reg:linear", "reg:squarederror", "reg:gamma", "reg:tweedie", "reg:quantileerror", "count:poisson",
+       "survival:cox"],
+     &["auc", "aucpr", "error", "logloss", "merror", "mlogloss", "ndcg", "map"]),
+    (&["binary:logistic", "binary:logitraw"], &["merror", "mlogloss"]),
+    (&["multi:softmax", "multi:softprob"], &["auc", "aucpr", "error", "logloss"]),
+    (&["rank:pairwise", "rank:ndcg"], &["merror", "mlogloss"]),
+];
+
+/// Type of feature importance calculated by [`feature_importance`](struct.Booster.html#method.feature_importance).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportanceType {
+    /// Number of times a feature is used to split the data.
+    Weight,
+
+    /// Average gain of splits which use the feature.
+    Gain,
+
+    /// Average coverage (number of samples affected) of splits which use the feature.
+    Cover,
+
+    /// Total gain of splits which use the feature.
+    TotalGain,
+
+    /// Total coverage of splits which use the feature.
+    TotalCover,
+}
+
+impl fmt::Display for ImportanceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ImportanceType::Weight => "weight",
+            ImportanceType::Gain => "gain",
+            ImportanceType::Cover => "cover",
+            ImportanceType::TotalGain => "total_gain",
+            ImportanceType::TotalCover => "total_cover",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Kind of task a model was trained for, from [`task_type`](struct.Booster.html#method.task_type).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskType {
+    /// Classification into given number of classes.
+    Classification { n_classes: usize },
+
+    /// Regression, predicting a continuous target.
+    Regression,
+
+    /// Learning to rank, predicting scores to order the rows of each query group.
+    Ranking,
+
+    /// Survival analysis, predicting (censored) survival times or hazard ratios.
+    Survival,
+}
+
+/// Split statistics of a single feature, from [`feature_report`](struct.Booster.html#method.feature_report).
+///
+/// Each score matches the [`ImportanceType`](enum.ImportanceType.html) of the same name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureStat {
+    /// Name of the feature, as set on the training matrix, or `fN` (where `N` is the feature index).
+    pub name: String,
+
+    /// Number of times the feature is used to split the data.
+    pub weight: f32,
+
+    /// Average gain of splits which use the feature.
+    pub gain: f32,
+
+    /// Average coverage of splits which use the feature.
+    pub cover: f32,
+
+    /// Total gain of splits which use the feature.
+    pub total_gain: f32,
+
+    /// Total coverage of splits which use the feature.
+    pub total_cover: f32,
+}
+
+/// Progress of a single boosting round during [`train`](struct.Booster.html#method.train), passed to the
+/// training parameters' `progress_fn`.
+#[derive(Clone, Debug)]
+pub struct IterationReport {
+    /// Boosting round this report is for (starting from 0).
+    pub round: u32,
+
+    /// Evaluation results for this round, as pairs of `<dataset>-<metric>` names and scores, e.g.
+    /// `("test-rmse", 0.5)`. Empty if no evaluation sets were given.
+    pub metrics: Vec<(String, f32)>,
+
+    /// Time elapsed since training started.
+    pub elapsed: Duration,
+}
+
+/// Maps a feature index to a name and type, used when dumping models as text.
+///
+/// See [dump_model](struct.Booster.html#method.dump_model) for usage.
+pub struct FeatureMap(BTreeMap<u32, (String, FeatureType)>);
+
+impl FeatureMap {
+    /// Read a `FeatureMap` from a file at given path.
+    ///
+    /// File should contain one feature definition per line, and be of the form:
+    /// ```text
+    /// <number>\t<name>\t<type>\n
+    /// ```
+    ///
+    /// Type should be one of:
+    /// * `i` - binary feature
+    /// * `q` - quantitative feature
+    /// * `int` - integer features
+    /// * `c` - categorical features
+    ///
+    /// E.g.:
+    /// ```text
+    /// 0   age int
+    /// 1   is-parent?=yes  i
+    /// 2   is-parent?=no   i
+    /// 3   income  int
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<FeatureMap> {
+        let file = File::open(path)?;
+        let mut features: FeatureMap = FeatureMap(BTreeMap::new());
+
+        for (i, line) in BufReader::new(&file).lines().enumerate() {
+            let line = line?;
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() != 3 {
+                let msg = format!("Unable to parse features from line {}, expected 3 tab separated values", i+1);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+
+            assert_eq!(parts.len(), 3);
+            let feature_num: u32 = match parts[0].parse() {
+                Ok(num)  => num,
+                Err(err) => {
+                    let msg = format!("Unable to parse features from line {}, could not parse feature number: {}",
+                                      i+1, err);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+                }
+            };
+
+            let feature_name = &parts[1];
+            let feature_type = match FeatureType::from_str(&parts[2]) {
+                Ok(feature_type) => feature_type,
+                Err(msg)         => {
+                    let msg = format!("Unable to parse features from line {}: {}", i+1, msg);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+                }
+            };
+            features.0.insert(feature_num, (feature_name.to_string(), feature_type));
+        }
+        Ok(features)
+    }
+}
+
+/// Indicates the type of a feature, used when dumping models as text, or to mark categorical features of a
+/// [`DMatrix`](struct.DMatrix.html#method.set_feature_types).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeatureType {
+    /// Binary indicator feature.
+    Binary,
+
+    /// Quantitative feature (e.g. age, time, etc.), can be missing.
+    Quantitative,
+
+    /// Integer feature (when hinted, decision boundary will be integer).
+    Integer,
+
+    /// Categorical feature, with non-negative integer values identifying each category.
+    Categorical,
+}
+
+impl FromStr for FeatureType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "i"           => Ok(FeatureType::Binary),
+            // 'float' is written by the Python package for numeric pandas columns
+            "q" | "float" => Ok(FeatureType::Quantitative),
+            "int"         => Ok(FeatureType::Integer),
+            "c"           => Ok(FeatureType::Categorical),
+            _             => Err(format!("unrecognised feature type '{}', must be one of: 'i', 'q', 'int', 'c'", s))
+        }
+    }
+}
+
+impl fmt::Display for FeatureType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            FeatureType::Binary => "i",
+            FeatureType::Quantitative => "q",
+            FeatureType::Integer => "int",
+            FeatureType::Categorical => "c",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parameters::{self, dart, learning, linear, tree};
+
+    fn read_train_matrix() -> XGBResult<DMatrix> {
+        DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train")
+    }
+
+    /// Logger recording every message logged by any test, for checking what training logs.
+    struct CaptureLogger;
+
+    static CAPTURED_LOGS: ::std::sync::Mutex<Vec<String>> = ::std::sync::Mutex::new(Vec::new());
+    static CAPTURE_LOGGER: CaptureLogger = CaptureLogger;
+
+    impl ::log::Log for CaptureLogger {
+        fn enabled(&self, _metadata: &::log::Metadata) -> bool { true }
+        fn log(&self, record: &::log::Record) {
+            CAPTURED_LOGS.lock().unwrap().push(record.args().to_string());
+        }
+        fn flush(&self) {}
+    }
+
+    /// Start capturing log messages (of info level and above) into `CAPTURED_LOGS`.
+    fn capture_logs() {
+        let _ = ::log::set_logger(&CAPTURE_LOGGER);
+        ::log::set_max_level(::log::LevelFilter::Info);
+    }
+
+    fn load_test_booster() -> Booster {
+        let dmat = read_train_matrix().expect("Reading train matrix failed");
+        Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat]).expect("Creating Booster failed")
+    }
+
+    #[test]
+    fn set_booster_param() {
+        let mut booster = load_test_booster();
+        let res = booster.set_param("key", "value");
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn load_rabit_version() {
+        let version = load_test_booster().load_rabit_checkpoint().unwrap();
+        assert_eq!(version, 0);
+    }
+
+    #[test]
+    fn get_set_attr() {
+        let mut booster = load_test_booster();
+        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
+        assert_eq!(attr, None);
+
+        booster.set_attribute("foo", "bar").expect("Setting attribute failed");
+        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
+        assert_eq!(attr, Some("bar".to_owned()));
+    }
+
+    #[test]
+    fn save_and_load_from_buffer() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat_train]).unwrap();
+        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
+        assert_eq!(attr, None);
+
+        booster.set_attribute("foo", "bar").expect("Setting attribute failed");
+        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
+        assert_eq!(attr, Some("bar".to_owned()));
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("test-xgboost-model");
+        booster.save(&path).expect("saving booster");
+        drop(booster);
+        let bytes = std::fs::read(&path).expect("read saved booster file");
+        let booster = Booster::load_buffer(&bytes[..]).expect("load booster from buffer");
+        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
+        assert_eq!(attr, Some("bar".to_owned()));
+    }
+
+    #[test]
+    fn save_and_load_json() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat_train]).unwrap();
+        for i in 0..5 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let bin_path = dir.path().join("model.bin");
+        let json_path = dir.path().join("model.json");
+        let ubj_path = dir.path().join("model.ubj");
+        booster.save(&bin_path).expect("saving binary booster");
+        booster.save_json(&json_path).expect("saving json booster");
+        booster.save_ubj(&ubj_path).expect("saving ubj booster");
+
+        let json = std::fs::read_to_string(&json_path).expect("reading json model");
+        assert!(json.starts_with('{'));
+
+        let expected = Booster::load(&bin_path).unwrap().predict(&dmat_test).unwrap();
+        assert_eq!(Booster::load(&json_path).unwrap().predict(&dmat_test).unwrap(), expected);
+        assert_eq!(Booster::load(&ubj_path).unwrap().predict(&dmat_test).unwrap(), expected);
+    }
+
+    #[test]
+    fn load_python_json_model() {
+        // single tree splitting on x0 < 0.5, saved with an estimated (non-default) base_score of 0.35
+        let booster = Booster::load("tests/data/python_binary_logistic.json").unwrap();
+        assert_eq!(booster.num_features().unwrap(), 2);
+        assert_eq!(booster.feature_names().unwrap(), vec!["x0", "x1"]);
+        assert!((booster.base_score().unwrap() - 0.35).abs() < 1e-6);
+
+        let data = [0.0, 1.0,
+                    1.0, 0.0,
+                    f32::NAN, 1.0];
+        let dmat = DMatrix::from_dense_with_missing(&data, 3, f32::NAN).unwrap();
+        let preds = booster.predict(&dmat).unwrap();
+        let expected = [0.265_214_6, 0.445_458_1, 0.265_214_6];
+        for (pred, expected) in preds.iter().zip(&expected) {
+            assert!((pred - expected).abs() < 1e-5, "prediction {} != {}", pred, expected);
+        }
+    }
+
+    #[test]
+    fn base_score() {
+        let dmat_train = read_train_matrix().unwrap();
+        let train = |learning_params: learning::LearningTaskParameters| {
+            let booster_params = parameters::BoosterParametersBuilder::default()
+                .learning_params(learning_params)
+                .verbose(false)
+                .build().unwrap();
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .booster_params(booster_params)
+                .dtrain(&dmat_train)
+                .boost_rounds(2)
+                .build().unwrap();
+            Booster::train(&training_params).unwrap()
+        };
+
+        let booster = train(learning::LearningTaskParameters::default());
+        assert!(booster.base_score().unwrap().is_finite());
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .base_score(0.3)
+            .build().unwrap();
+        let booster = train(learning_params);
+        assert_eq!(booster.base_score().unwrap(), 0.3);
+    }
+
+    #[test]
+    fn evaluate_weighted() {
+        let dmat_train = read_train_matrix().unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train]).unwrap();
+        booster.update(&dmat_train, 0).expect("update failed");
+
+        let mut dmat_weighted = read_train_matrix().unwrap();
+        let weights: Vec<f32> = (0..dmat_weighted.num_rows()).map(|i| (i % 10) as f32).collect();
+        dmat_weighted.set_weights(&weights).unwrap();
+
+        let unweighted = booster.evaluate(&dmat_train).unwrap()["logloss"];
+        let weighted = booster.evaluate(&dmat_weighted).unwrap()["logloss"];
+        assert_ne!(weighted, unweighted);
+
+        // manually weighted average of the per-row log loss should match XGBoost's
+        let preds = booster.predict(&dmat_train).unwrap();
+        let labels = dmat_train.get_labels().unwrap();
+        let (mut total, mut total_weight) = (0.0f64, 0.0f64);
+        for ((pred, label), weight) in preds.iter().zip(labels).zip(&weights) {
+            let p = (*pred as f64).clamp(1e-16, 1.0 - 1e-16);
+            let loss = if *label > 0.5 { -p.ln() } else { -(1.0 - p).ln() };
+            total += loss * *weight as f64;
+            total_weight += *weight as f64;
+        }
+        assert!(((total / total_weight) as f32 - weighted).abs() < 1e-3);
+    }
+
+    #[test]
+    fn shared_dmats_outlive_bindings() {
+        let dmat_train = Rc::new(read_train_matrix().unwrap());
+        let expected_rows = dmat_train.num_rows();
+
+        let params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_shared_dmats(&params, slice::from_ref(&dmat_train)).unwrap();
+        booster.update(&dmat_train, 0).expect("update failed");
+        drop(dmat_train);
+
+        let dmat = booster.shared_dmats()[0].clone();
+        assert_eq!(Rc::strong_count(&dmat), 2);
+        let preds = booster.predict(&dmat).unwrap();
+        assert_eq!(preds.len(), expected_rows);
+    }
+
+    #[test]
+    fn train_categorical() {
+        // label is determined by which of 6 categories feature 0 is in, feature 1 is noise
+        let num_rows = 120;
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        for i in 0..num_rows {
+            let category = i % 6;
+            data.extend_from_slice(&[category as f32, (i % 7) as f32]);
+            labels.push(if category == 1 || category == 4 { 1.0 } else { 0.0 });
+        }
+        let mut dtrain = DMatrix::from_dense_with_missing(&data, num_rows, f32::NAN).unwrap();
+        dtrain.set_labels(&labels).unwrap();
+        dtrain.set_feature_types(&[FeatureType::Categorical, FeatureType::Quantitative]).unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .tree_method(tree::TreeMethod::Hist)
+            .max_cat_to_onehot(Some(4))
+            .build()
+            .unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dtrain)
+            .boost_rounds(5)
+            .build()
+            .unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        fn find<'a>(value: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+            match *value {
+                serde_json::Value::Object(ref map) => map.get(key)
+                    .or_else(|| map.values().filter_map(|v| find(v, key)).next()),
+                serde_json::Value::Array(ref values) => values.iter().filter_map(|v| find(v, key)).next(),
+                _ => None,
+            }
+        }
+        let config: serde_json::Value = serde_json::from_str(&booster.save_config().unwrap()).unwrap();
+        assert_eq!(find(&config, "max_cat_to_onehot").and_then(|v| v.as_str()), Some("4"));
+
+        let preds = booster.predict(&dtrain).unwrap();
+        for (pred, label) in preds.iter().zip(&labels) {
+            assert!((pred - label).abs() < 0.5);
+        }
+    }
+
+    #[test]
+    fn feature_importance_uses_training_names() {
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        for i in 0..100 {
+            let x = (i % 2 + 1) as f32;
+            data.extend_from_slice(&[x, ((i * 7) % 5 + 1) as f32]);
+            labels.push(x - 1.0);
+        }
+        let mut dtrain = DMatrix::from_dense(&data, 100).unwrap();
+        dtrain.set_labels(&labels).unwrap();
+        dtrain.set_feature_names(&["signal", "noise"]).unwrap();
+
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dtrain)
+            .boost_rounds(3)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        assert_eq!(booster.feature_names().unwrap(), vec!["signal", "noise"]);
+
+        let importance = booster.feature_importance(ImportanceType::Gain).unwrap();
+        assert!(importance.contains_key("signal"));
+        assert!(importance.keys().all(|name| name == "signal" || name == "noise"));
+    }
+
+    #[test]
+    fn feature_importance_default_names() {
+        let booster = {
+            let dmat_train = read_train_matrix().unwrap();
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .booster_params(parameters::BoosterParametersBuilder::default().verbose(false).build().unwrap())
+                .dtrain(&dmat_train)
+                .boost_rounds(2)
+                .build().unwrap();
+            Booster::train(&training_params).unwrap()
+        };
+        assert!(booster.feature_names().unwrap().is_empty());
+
+        let importance = booster.feature_importance(ImportanceType::Weight).unwrap();
+        assert!(!importance.is_empty());
+        assert!(importance.keys().all(|name| name.starts_with('f')));
+        assert!(importance.values().all(|score| *score >= 1.0));
+    }
+
+    #[test]
+    fn feature_report() {
+        let dmat_train = read_train_matrix().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(parameters::BoosterParametersBuilder::default().verbose(false).build().unwrap())
+            .dtrain(&dmat_train)
+            .boost_rounds(3)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let report = booster.feature_report().unwrap();
+        let num_splits = booster.trees().unwrap()
+            .iter()
+            .flat_map(|tree| tree.nodes().iter())
+            .filter(|node| matches!(**node, Node::Split { .. }))
+            .count();
+        assert_eq!(report.iter().map(|stat| stat.weight).sum::<f32>(), num_splits as f32);
+
+        let total_gain = booster.feature_importance(ImportanceType::TotalGain).unwrap();
+        assert_eq!(report.len(), total_gain.len());
+        for stat in &report {
+            assert!((stat.total_gain - total_gain[&stat.name]).abs() <= 1e-3 * stat.total_gain.abs().max(1.0));
+            assert!((stat.gain * stat.weight - stat.total_gain).abs() <= 1e-3 * stat.total_gain.abs().max(1.0));
+        }
+    }
+
+    #[test]
+    fn tree_method_auto_resolved() {
+        capture_logs();
+        let dmat_train = read_train_matrix().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(parameters::BoosterParametersBuilder::default().verbose(false).build().unwrap())
+            .dtrain(&dmat_train)
+            .boost_rounds(2)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let method = booster.tree_method().unwrap().unwrap();
+        assert!(["exact", "approx", "hist"].contains(&method.as_str()));
+        let expected = format!("tree_method=auto resolved to: {}", method);
+        assert!(CAPTURED_LOGS.lock().unwrap().contains(&expected));
+    }
+
+    #[test]
+    fn train_incompatible_metric() {
+        capture_logs();
+        let dmat_train = read_train_matrix().unwrap();
+        let train = |strict_metrics: bool| {
+            let learning_params = learning::LearningTaskParametersBuilder::default()
+                .objective(learning::Objective::RegLinear)
+                .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::AUC]))
+                .build().unwrap();
+            let booster_params = parameters::BoosterParametersBuilder::default()
+                .learning_params(learning_params)
+                .verbose(false)
+                .build().unwrap();
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .booster_params(booster_params)
+                .dtrain(&dmat_train)
+                .strict_metrics(strict_metrics)
+                .boost_rounds(1)
+                .build().unwrap();
+            Booster::train(&training_params)
+        };
+
+        let expected = "evaluation metric 'auc' is not suited to objective 'reg:linear'";
+        assert!(train(false).is_ok());
+        assert!(CAPTURED_LOGS.lock().unwrap().iter().any(|line| line == expected));
+        assert_eq!(train(true).map(|_| ()), Err(XGBError::InvalidParameter(expected.to_owned())));
+    }
+
+    #[test]
+    fn train_verbose_eval() {
+        capture_logs();
+        let dmat_train = read_train_matrix().unwrap();
+        let train = |verbose_eval: Option<u32>, eval_name: &str| {
+            let evaluation_sets = &[(&dmat_train, eval_name)];
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .booster_params(parameters::BoosterParametersBuilder::default().verbose(false).build().unwrap())
+                .dtrain(&dmat_train)
+                .evaluation_sets(Some(evaluation_sets))
+                .verbose_eval(verbose_eval)
+                .boost_rounds(4)
+                .build().unwrap();
+            Booster::train(&training_params).unwrap()
+        };
+        // other tests log concurrently, so only count lines mentioning each run's own evaluation set
+        let count_logged = |eval_name: &str| {
+            let prefix = format!("{}-", eval_name);
+            CAPTURED_LOGS.lock().unwrap().iter().filter(|line| line.contains(&prefix)).count()
+        };
+
+        let booster = train(Some(0), "quiet");
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 4);
+        assert_eq!(booster.eval_history()["quiet-rmse"].len(), 4);
+        assert_eq!(count_logged("quiet"), 0);
+
+        train(None, "silent");
+        assert_eq!(count_logged("silent"), 0);
+
+        train(Some(2), "sparse");
+        assert_eq!(count_logged("sparse"), 2);
+    }
+
+    #[test]
+    fn output_dim() {
+        let data = [1.0, 2.0,
+                    2.0, 1.0,
+                    1.0, 1.0];
+        let mut dtrain = DMatrix::from_dense(&data, 3).unwrap();
+        dtrain.set_labels(&[0.0, 1.0, 2.0]).unwrap();
+
+        let train = |objective: learning::Objective| {
+            let learning_params = learning::LearningTaskParametersBuilder::default()
+                .objective(objective)
+                .build()
+                .unwrap();
+            let params = parameters::BoosterParametersBuilder::default()
+                .learning_params(learning_params)
+                .verbose(false)
+                .build()
+                .unwrap();
+            let mut booster = Booster::new_with_cached_dmats(&params, &[&dtrain]).unwrap();
+            booster.update(&dtrain, 0).expect("update failed");
+            booster
+        };
+
+        let booster = train(learning::Objective::MultiSoftprob(3));
+        assert_eq!(booster.output_dim().unwrap(), 3);
+        assert_eq!(booster.predict(&dtrain).unwrap().len(), 3 * 3);
+
+        let booster = train(learning::Objective::MultiSoftmax(3));
+        assert_eq!(booster.output_dim().unwrap(), 1);
+
+        let booster = train(learning::Objective::RegLinear);
+        assert_eq!(booster.output_dim().unwrap(), 1);
+    }
+
+    #[test]
+    fn num_trees() {
+        let dmat_train = read_train_matrix().unwrap();
+        let train = |booster_type: parameters::BoosterType| {
+            let booster_params = parameters::BoosterParametersBuilder::default()
+                .booster_type(booster_type)
+                .verbose(false)
+                .build().unwrap();
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .booster_params(booster_params)
+                .dtrain(&dmat_train)
+                .boost_rounds(4)
+                .build().unwrap();
+            Booster::train(&training_params).unwrap()
+        };
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .num_parallel_tree(2)
+            .build().unwrap();
+        let gbtree = train(parameters::BoosterType::Tree(tree_params));
+        assert_eq!(gbtree.num_boosted_rounds().unwrap(), 4);
+        assert_eq!(gbtree.num_trees().unwrap(), 8);
+
+        let dart_params = dart::DartBoosterParametersBuilder::default()
+            .rate_drop(0.5)
+            .build().unwrap();
+        let dart = train(parameters::BoosterType::Dart(dart_params));
+        assert_eq!(dart.num_trees().unwrap(), 4);
+
+        let linear = train(parameters::BoosterType::Linear(Default::default()));
+        assert_eq!(linear.num_trees().unwrap(), 0);
+    }
+
+    #[test]
+    fn feature_types_after_save_and_load() {
+        let num_rows = 60;
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        for i in 0..num_rows {
+            let category = (i % 3) as f32;
+            data.extend_from_slice(&[category, (i % 7 + 1) as f32]);
+            labels.push(if category == 1.0 { 1.0 } else { 0.0 });
+        }
+        let mut dtrain = DMatrix::from_dense_with_missing(&data, num_rows, f32::NAN).unwrap();
+        dtrain.set_labels(&labels).unwrap();
+        dtrain.set_feature_types(&[FeatureType::Categorical, FeatureType::Quantitative]).unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .tree_method(tree::TreeMethod::Hist)
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dtrain)
+            .boost_rounds(2)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        assert_eq!(booster.feature_types().unwrap(), vec![FeatureType::Categorical, FeatureType::Quantitative]);
+
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = tmp_dir.path().join("model.json");
+        booster.save(&path).unwrap();
+        let loaded = Booster::load(&path).unwrap();
+        assert_eq!(loaded.feature_types().unwrap()[0], FeatureType::Categorical);
+
+        assert!(load_test_booster().feature_types().unwrap().is_empty());
+    }
+
+    #[test]
+    fn with_nthread() {
+        let dmat_train = read_train_matrix().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(parameters::BoosterParametersBuilder::default().verbose(false).build().unwrap())
+            .dtrain(&dmat_train)
+            .boost_rounds(3)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        let preds = booster.predict(&dmat_train).unwrap();
+
+        let booster = booster.with_nthread(1).unwrap();
+        assert_eq!(booster.predict(&dmat_train).unwrap(), preds);
+        assert!(booster.save_config().unwrap().contains("\"nthread\":\"1\""));
+
+        let booster = booster.with_nthread(4).unwrap();
+        assert_eq!(booster.predict(&dmat_train).unwrap(), preds);
+    }
+
+    #[test]
+    fn predict_with_ids() {
+        let dmat_train = read_train_matrix().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(parameters::BoosterParametersBuilder::default().verbose(false).build().unwrap())
+            .dtrain(&dmat_train)
+            .boost_rounds(2)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let dmat = dmat_train.head(5).unwrap();
+        let ids = [50, 40, 30, 20, 10];
+        let preds = booster.predict(&dmat).unwrap();
+
+        let paired = booster.predict_with_ids(&dmat, &ids).unwrap();
+        assert_eq!(paired.len(), 5);
+        for (i, (id, row_preds)) in paired.iter().enumerate() {
+            assert_eq!(*id, ids[i]);
+            assert_eq!(row_preds, &vec![preds[i]]);
+        }
+
+        assert_eq!(booster.predict_with_ids(&dmat, &ids[..3]).unwrap_err(),
+                   XGBError::DimensionMismatch { expected: 5, got: 3 });
+    }
+
+    #[test]
+    fn predict_labels() {
+        let dmat_train = read_train_matrix().unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(2)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let labels = booster.predict_labels(&dmat_train, 0.5).unwrap();
+        let expected: Vec<u32> = booster.predict(&dmat_train).unwrap()
+            .iter()
+            .map(|p| if *p > 0.5 { 1 } else { 0 })
+            .collect();
+        assert_eq!(labels, expected);
+        assert!(labels.contains(&0) && labels.contains(&1));
+
+        // multiclass probabilities are reduced to the most probable class
+        let data = [1.0, 2.0,
+                    2.0, 1.0,
+                    3.0, 3.0];
+        let mut dtrain = DMatrix::from_dense(&data, 3).unwrap();
+        dtrain.set_labels(&[0.0, 1.0, 2.0]).unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::MultiSoftprob(3))
+            .build().unwrap();
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .min_child_weight(0.0)
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dtrain)
+            .boost_rounds(10)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        assert_eq!(booster.predict_labels(&dtrain, 0.5).unwrap(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn get_attribute_names() {
+        let mut booster = load_test_booster();
+        let attrs = booster.get_attribute_names().expect("Getting attributes failed");
+        assert_eq!(attrs, Vec::<String>::new());
+
+        booster.set_attribute("foo", "bar").expect("Setting attribute failed");
+        booster.set_attribute("another", "another").expect("Setting attribute failed");
+        booster.set_attribute("4", "4").expect("Setting attribute failed");
+        booster.set_attribute("an even longer attribute name?", "").expect("Setting attribute failed");
+
+        let mut expected = vec!["foo", "another", "4", "an even longer attribute name?"];
+        expected.sort();
+        let mut attrs = booster.get_attribute_names().expect("Getting attributes failed");
+        attrs.sort();
+        assert_eq!(attrs, expected);
+    }
+
+    #[test]
+    fn predict() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(2)
+            .eta(1.0)
+            .build()
+            .unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::MAPCutNegative(4),
+                                                         learning::EvaluationMetric::LogLoss,
+                                                         learning::EvaluationMetric::BinaryErrorRate(0.5)]))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+
+        for i in 0..10 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let train_metrics = booster.evaluate(&dmat_train).unwrap();
+        assert_eq!(*train_metrics.get("logloss").unwrap(), 0.006634271);
+        assert_eq!(*train_metrics.get("map@4-").unwrap(), 0.0012738854);
+
+        let test_metrics = booster.evaluate(&dmat_test).unwrap();
+        assert_eq!(*test_metrics.get("logloss").unwrap(), 0.006919953);
+        assert_eq!(*test_metrics.get("map@4-").unwrap(), 0.005154639);
+
+        let v = booster.predict(&dmat_test).unwrap();
+        assert_eq!(v.len(), dmat_test.num_rows());
+
+        // first 10 predictions
+        let expected_start = [0.0050151693,
+                              0.9884467,
+                              0.0050151693,
+                              0.0050151693,
+                              0.026636455,
+                              0.11789363,
+                              0.9884467,
+                              0.01231471,
+                              0.9884467,
+                              0.00013656063];
+
+        // last 10 predictions
+        let expected_end = [0.002520344,
+                            0.00060917926,
+                            0.99881005,
+                            0.00060917926,
+                            0.00060917926,
+                            0.00060917926,
+                            0.00060917926,
+                            0.9981102,
+                            0.002855195,
+                            0.9981102];
+        let eps = 1e-6;
+
+        for (pred, expected) in v.iter().zip(&expected_start) {
+            println!("predictions={}, expected={}", pred, expected);
+            assert!(pred - expected < eps);
+        }
+
+        for (pred, expected) in v[v.len()-10..].iter().zip(&expected_end) {
+            println!("predictions={}, expected={}", pred, expected);
+            assert!(pred - expected < eps);
+        }
+    }
+
+    #[test]
+    fn set_predictor_on_loaded_model() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(2)
+            .eta(1.0)
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train]).unwrap();
+        for i in 0..5 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+        let expected = booster.predict(&dmat_test).unwrap();
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("model.bin");
         booster.save(&path).expect("saving booster");
-        drop(booster);
-        let bytes = std::fs::read(&path).expect("read saved booster file");
-        let booster = Booster::load_buffer(&bytes[..]).expect("load booster from buffer");
-        let attr = booster.get_attribute("foo").expect("Getting attribute failed");
-        assert_eq!(attr, Some("bar".to_owned()));
+
+        let mut booster = Booster::load(&path).expect("loading booster");
+        booster.set_predictor(tree::Predictor::Cpu).expect("setting predictor failed");
+        assert_eq!(booster.predict(&dmat_test).unwrap(), expected);
+    }
+
+    #[test]
+    fn refresh() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let mut dmat_flipped = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let flipped: Vec<f32> = dmat_train.get_labels().unwrap().iter().map(|l| 1.0 - l).collect();
+        dmat_flipped.set_labels(&flipped).unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_flipped]).unwrap();
+        for i in 0..5 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+        let before = booster.predict(&dmat_train).unwrap();
+
+        booster.refresh(&dmat_flipped).expect("refresh failed");
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 5);
+        assert_ne!(booster.predict(&dmat_train).unwrap(), before);
+    }
+
+    #[test]
+    fn prune() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(6)
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(5)
+            .build().unwrap();
+        let mut booster = Booster::train(&training_params).unwrap();
+        assert_eq!(booster.num_features().unwrap(), dmat_train.num_cols());
+
+        // text dumps have one node per line, indented by one tab per level of depth
+        let count_nodes = |dump: &str| dump.lines().filter(|l| !l.starts_with("booster")).count();
+        let max_depth = |dump: &str| dump.lines().map(|l| l.chars().take_while(|c| *c == '\t').count()).max();
+
+        let before = booster.dump_model(false, None).unwrap();
+        assert!(max_depth(&before).unwrap() > 3);
+
+        booster.prune(3).expect("prune failed");
+        let after = booster.dump_model(false, None).unwrap();
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 5);
+        assert!(count_nodes(&after) < count_nodes(&before));
+        assert!(max_depth(&after).unwrap() <= 3);
+    }
+
+    #[test]
+    fn gradients() {
+        let dmat_train = read_train_matrix().unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::RegLinear)
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let (gradient, hessian) = booster.gradients(&dmat_train).unwrap();
+        let preds = booster.predict(&dmat_train).unwrap();
+        let labels = dmat_train.get_labels().unwrap();
+        assert_eq!(gradient.len(), dmat_train.num_rows());
+        for ((grad, pred), label) in gradient.iter().zip(&preds).zip(labels) {
+            assert!((grad - (pred - label)).abs() < 1e-6);
+        }
+        assert!(hessian.iter().all(|hess| *hess == 1.0));
+    }
+
+    #[test]
+    fn task_type() {
+        let data = [1.0, 0.0,
+                    0.0, 1.0,
+                    1.0, 1.0,
+                    0.0, 0.0,
+                    1.0, 0.0,
+                    0.0, 1.0];
+        let mut dtrain = DMatrix::from_dense(&data, 6).unwrap();
+        dtrain.set_labels(&[1.0, 0.0, 1.0, 0.0, 1.0, 1.0]).unwrap();
+        dtrain.set_group_sizes(&[2, 1, 3]).unwrap();
+
+        let train = |objective: learning::Objective| {
+            let learning_params = learning::LearningTaskParametersBuilder::default()
+                .objective(objective)
+                .build()
+                .unwrap();
+            let params = parameters::BoosterParametersBuilder::default()
+                .learning_params(learning_params)
+                .verbose(false)
+                .build()
+                .unwrap();
+            let mut booster = Booster::new_with_cached_dmats(&params, &[&dtrain]).unwrap();
+            booster.update(&dtrain, 0).expect("update failed");
+            booster.task_type().unwrap()
+        };
+
+        assert_eq!(train(learning::Objective::BinaryLogistic), TaskType::Classification { n_classes: 2 });
+        assert_eq!(train(learning::Objective::MultiSoftprob(3)), TaskType::Classification { n_classes: 3 });
+        assert_eq!(train(learning::Objective::RegLinear), TaskType::Regression);
+        assert_eq!(train(learning::Objective::RankNdcg), TaskType::Ranking);
+    }
+
+    #[test]
+    fn predict_grouped() {
+        let data = [1.0, 0.0,
+                    0.0, 1.0,
+                    1.0, 1.0,
+                    0.0, 0.0,
+                    1.0, 0.0,
+                    0.0, 1.0];
+        let mut dtrain = DMatrix::from_dense(&data, 6).unwrap();
+        dtrain.set_labels(&[1.0, 0.0, 1.0, 0.0, 2.0, 1.0]).unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::RankPairwise)
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dtrain]).unwrap();
+        assert!(booster.predict_grouped(&dtrain).is_err());
+
+        dtrain.set_group_sizes(&[2, 1, 3]).unwrap();
+        booster.update(&dtrain, 0).expect("update failed");
+
+        let grouped = booster.predict_grouped(&dtrain).unwrap();
+        let lengths: Vec<usize> = grouped.iter().map(|group| group.len()).collect();
+        assert_eq!(lengths, vec![2, 1, 3]);
+        assert_eq!(grouped.concat(), booster.predict(&dtrain).unwrap());
+    }
+
+    #[test]
+    fn train_label_gain() {
+        let data = [1.0, 0.0,
+                    0.0, 1.0,
+                    1.0, 1.0,
+                    0.0, 0.0,
+                    1.0, 0.0,
+                    0.0, 1.0];
+        let mut dtrain = DMatrix::from_dense(&data, 6).unwrap();
+        dtrain.set_labels(&[1.0, 0.0, 3.0, 0.0, 2.0, 1.0]).unwrap();
+        dtrain.set_group_sizes(&[2, 1, 3]).unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::RankPairwise)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::NDCG]))
+            .label_gain(vec![0.0, 1.0, 3.0, 7.0])
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dtrain]).unwrap();
+        for i in 0..5 {
+            booster.update(&dtrain, i).expect("update failed");
+        }
+
+        let config = booster.save_config().unwrap();
+        assert!(config.contains("\"label_gain\""), "label_gain missing from config: {}", config);
+        assert_eq!(booster.predict(&dtrain).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn train_max_cached_hist_node() {
+        let dmat_train = read_train_matrix().unwrap();
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .tree_method(tree::TreeMethod::Hist)
+            .max_cached_hist_node(Some(16))
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .verbose(false)
+            .build()
+            .unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(3)
+            .build()
+            .unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let config = booster.save_config().unwrap();
+        assert!(config.contains("\"max_cached_hist_node\":\"16\""), "unexpected config: {}", config);
+        assert_eq!(booster.predict(&dmat_train).unwrap().len(), dmat_train.num_rows());
+    }
+
+    #[test]
+    fn predict_margin_and_proba() {
+        let dmat_train = read_train_matrix().unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .build().unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build().unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let check = |booster: &Booster| {
+            let (margin, proba) = booster.predict_margin_and_proba(&dmat_test).unwrap();
+            assert_eq!(margin, booster.predict_margin(&dmat_test).unwrap());
+            let expected_proba = booster.predict(&dmat_test).unwrap();
+            assert_eq!(proba.len(), expected_proba.len());
+            for (p, expected_p) in proba.iter().zip(&expected_proba) {
+                assert!((p - expected_p).abs() < 1e-6);
+            }
+            margin
+        };
+        let all_rounds = check(&booster);
+
+        // as after early stopping, both halves only use the default iteration range
+        booster.set_default_iteration_range((0, 2));
+        assert_ne!(check(&booster), all_rounds);
+    }
+
+    #[test]
+    fn predict_trees_only() {
+        let dmat_train = read_train_matrix().unwrap();
+        let mut dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .build().unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build().unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        // trees alone, relative to the model's own bias
+        let trees = booster.predict_trees_only(&dmat_test).unwrap();
+        let margin = booster.predict_margin(&dmat_test).unwrap();
+        let bias = Link::Logistic.inverse(booster.base_score().unwrap());
+        for (tree, full) in trees.iter().zip(&margin) {
+            assert!((tree + bias - full).abs() < 1e-5);
+        }
+
+        // trees on top of another model's outputs
+        let base_margin: Vec<f32> = (0..dmat_test.num_rows()).map(|i| (i % 5) as f32 - 2.0).collect();
+        dmat_test.set_base_margin(&base_margin).unwrap();
+        let trees = booster.predict_trees_only(&dmat_test).unwrap();
+        let margin = booster.predict_margin(&dmat_test).unwrap();
+        for ((tree, base), full) in trees.iter().zip(&base_margin).zip(&margin) {
+            assert!((tree + base - full).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn predict_chunked() {
+        let dmat_train = read_train_matrix().unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat_train]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let expected = booster.predict(&dmat_test).unwrap();
+        assert_eq!(booster.predict_chunked(&dmat_test, 100).unwrap(), expected);
+        assert_eq!(booster.predict_chunked(&dmat_test, 7).unwrap(), expected);
+        assert_eq!(booster.predict_chunked(&dmat_test, dmat_test.num_rows()).unwrap(), expected);
+        assert!(booster.predict_chunked(&dmat_test, 0).is_err());
+
+        // one thread per chunk, so chunks are predicted concurrently
+        let mut booster = booster.with_nthread(1).unwrap();
+        assert_eq!(booster.nthread().unwrap(), 1);
+        assert_eq!(booster.predict_chunked(&dmat_test, 100).unwrap(), expected);
+
+        booster.set_default_iteration_range((0, 2));
+        assert_eq!(booster.predict_chunked(&dmat_test, 100).unwrap(), booster.predict(&dmat_test).unwrap());
+    }
+
+    #[test]
+    fn shap_base_value() {
+        let dmat_train = read_train_matrix().unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat_train]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let check = |booster: &Booster| {
+            let base_value = booster.shap_base_value().unwrap();
+            let (contribs, (_num_rows, num_cols)) = booster.predict_contributions(&dmat_test).unwrap();
+            for row in contribs.chunks(num_cols).take(100) {
+                assert!((row[num_cols - 1] - base_value).abs() < 1e-5);
+            }
+        };
+        check(&booster);
+        booster.set_default_iteration_range((0, 2));
+        check(&booster);
+    }
+
+    #[test]
+    fn tree_depths() {
+        let dmat_train = read_train_matrix().unwrap();
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(4)
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train]).unwrap();
+        for i in 0..5 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let depths = booster.tree_depths().unwrap();
+        assert_eq!(depths.len(), 5);
+        assert!(depths.iter().all(|depth| *depth <= 4));
+        assert!(depths.iter().any(|depth| *depth > 0));
+    }
+
+    #[test]
+    fn regularization_params() {
+        let dmat_train = read_train_matrix().unwrap();
+        let train = |tree_params: tree::TreeBoosterParameters| {
+            let params = parameters::BoosterParametersBuilder::default()
+                .booster_type(parameters::BoosterType::Tree(tree_params))
+                .verbose(false)
+                .build()
+                .unwrap();
+            let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train]).unwrap();
+            for i in 0..3 {
+                booster.update(&dmat_train, i).expect("update failed");
+            }
+            booster
+        };
+
+        let regularized = train(tree::TreeBoosterParametersBuilder::default()
+                                .gamma(1.5)
+                                .max_depth(2)
+                                .min_child_weight(5.0)
+                                .reg_lambda(20.0)
+                                .reg_alpha(0.5)
+                                .build()
+                                .unwrap());
+
+        // tree updaters save their training parameters under XGBoost's canonical names
+        fn find_train_param(value: &serde_json::Value) -> Option<&serde_json::Value> {
+            if let Some(train_param) = value.get("train_param") {
+                return Some(train_param);
+            }
+            match *value {
+                serde_json::Value::Object(ref map) => map.values().filter_map(find_train_param).next(),
+                serde_json::Value::Array(ref values) => values.iter().filter_map(find_train_param).next(),
+                _ => None,
+            }
+        }
+        let config = regularized.config_json().unwrap();
+        let train_param = find_train_param(&config).expect("no train_param in config");
+        let param = |name: &str| -> f32 { train_param[name].as_str().unwrap().parse().unwrap() };
+        assert_eq!(param("min_split_loss"), 1.5);
+        assert_eq!(param("max_depth"), 2.0);
+        assert_eq!(param("min_child_weight"), 5.0);
+        assert_eq!(param("reg_lambda"), 20.0);
+        assert_eq!(param("reg_alpha"), 0.5);
+
+        let default = train(tree::TreeBoosterParameters::default());
+        let max_depth = |booster: &Booster| booster.tree_depths().unwrap().into_iter().max().unwrap();
+        assert!(max_depth(&regularized) <= 2);
+        assert!(max_depth(&regularized) < max_depth(&default));
+    }
+
+    #[test]
+    fn dump_model_json_with_precision() {
+        let dmat_train = read_train_matrix().unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat_train]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let dumps = booster.dump_model_json_with_precision(true, Some(3)).unwrap();
+        assert_eq!(booster.dump_model_json_with_precision(true, None).unwrap(), booster.dump_model_json(true).unwrap());
+
+        fn check_precision(node: &serde_json::Value) {
+            for key in &["leaf", "split_condition"] {
+                if let Some(value) = node.get(*key) {
+                    let text = value.to_string();
+                    let decimals = text.split('.').nth(1).map_or(0, |d| d.len());
+                    assert!(decimals <= 3, "{} has more than 3 decimal places: {}", key, text);
+                }
+            }
+            if let Some(children) = node.get("children").and_then(|c| c.as_array()) {
+                children.iter().for_each(check_precision);
+            }
+        }
+        assert!(!dumps.is_empty());
+        for dump in &dumps {
+            check_precision(&serde_json::from_str(dump).unwrap());
+        }
+    }
+
+    #[test]
+    fn concat_trees() {
+        let dmat_train = read_train_matrix().unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build().unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let merged = Booster::concat_trees(&[&booster, &booster]).unwrap();
+        assert_eq!(merged.num_boosted_rounds().unwrap(), 6);
+        let expected = booster.predict(&dmat_test).unwrap();
+        for (pred, expected) in merged.predict(&dmat_test).unwrap().iter().zip(&expected) {
+            assert!((pred - expected).abs() < 1e-5);
+        }
+
+        let linear_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Linear(linear::LinearBoosterParameters::default()))
+            .verbose(false)
+            .build().unwrap();
+        let mut linear = Booster::new_with_cached_dmats(&linear_params, &[&dmat_train]).unwrap();
+        linear.update(&dmat_train, 0).expect("update failed");
+        assert!(Booster::concat_trees(&[&booster, &linear]).is_err());
+        assert!(Booster::concat_trees(&[]).is_err());
+    }
+
+    #[test]
+    fn staged_predict() {
+        let dmat_train = read_train_matrix().unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat_train]).unwrap();
+        for i in 0..4 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let stages = booster.staged_predict(&dmat_train).unwrap();
+        assert_eq!(stages.len(), 4);
+        assert!(stages.iter().all(|stage| stage.len() == dmat_train.num_rows()));
+        assert_eq!(stages[3], booster.predict(&dmat_train).unwrap());
+        for pair in stages.windows(2) {
+            assert_ne!(pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn predict_at_round() {
+        let dmat_train = read_train_matrix().unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat_train]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+        let snapshot = booster.predict_at_round(&dmat_train, 2).unwrap();
+        let all_rounds = booster.predict(&dmat_train).unwrap();
+        assert_eq!(booster.predict_at_round(&dmat_train, 3).unwrap(), all_rounds);
+
+        for i in 3..5 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+        assert_eq!(booster.predict_at_round(&dmat_train, 2).unwrap(), snapshot);
+        assert_ne!(booster.predict(&dmat_train).unwrap(), all_rounds);
+
+        assert!(booster.predict_at_round(&dmat_train, 0).is_err());
+        assert!(booster.predict_at_round(&dmat_train, 6).is_err());
+    }
+
+    #[test]
+    fn predict_feature_count_mismatch() {
+        let mut dtrain = DMatrix::from_dense(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 3).unwrap();
+        dtrain.set_labels(&[1.0, 2.0, 3.0]).unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dtrain]).unwrap();
+        booster.update(&dtrain, 0).expect("update failed");
+
+        let dwide = DMatrix::from_dense(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2).unwrap();
+        let expected = Err(XGBError::FeatureCountMismatch { model: 2, data: 3 });
+        assert_eq!(booster.predict(&dwide), expected);
+        assert_eq!(booster.predict_with_shape(&dwide).map(|_| ()), expected.map(|_: Vec<f32>| ()));
+        let mismatch = Some(XGBError::FeatureCountMismatch { model: 2, data: 3 });
+        assert_eq!(booster.predict_margin(&dwide).err(), mismatch);
+        assert_eq!(booster.predict_leaf(&dwide).err(), mismatch);
+        assert_eq!(booster.predict_contributions(&dwide).err(), mismatch);
+        booster.set_default_iteration_range((0, 1));
+        assert_eq!(booster.predict(&dwide).err(), mismatch);
+
+        let dnarrow = DMatrix::from_dense(&[1.0, 2.0], 2).unwrap();
+        assert_eq!(booster.predict(&dnarrow).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn predict_lenient() {
+        let data = [1.0, 2.0, 5.0,
+                    2.0, 1.0, 6.0,
+                    3.0, 2.0, 7.0,
+                    4.0, 1.0, 8.0];
+        let mut dtrain = DMatrix::from_dense(&data, 4).unwrap();
+        dtrain.set_labels(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dtrain]).unwrap();
+        for i in 0..5 {
+            booster.update(&dtrain, i).expect("update failed");
+        }
+        assert_eq!(booster.num_features().unwrap(), 3);
+
+        let truncated = [1.0, 2.0,
+                         4.0, 1.0];
+        let dtest = DMatrix::from_dense(&truncated, 2).unwrap();
+        let preds = booster.predict_lenient(&dtest).unwrap();
+
+        let missing_last = [1.0, 2.0, f32::NAN,
+                            4.0, 1.0, f32::NAN];
+        let dmissing = DMatrix::from_dense_with_missing(&missing_last, 2, f32::NAN).unwrap();
+        assert_eq!(preds, booster.predict(&dmissing).unwrap());
+        assert_eq!(booster.predict_lenient(&dtrain).unwrap(), booster.predict(&dtrain).unwrap());
+    }
+
+    #[test]
+    fn predict_2d_quantiles() {
+        // noisy linear data, so lower quantiles should be predicted below higher ones
+        let num_rows = 200;
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        for i in 0..num_rows {
+            let x = (i % 20 + 1) as f32;
+            data.push(x);
+            labels.push(x + ((i * 13) % 11) as f32 - 5.0);
+        }
+        let mut dtrain = DMatrix::from_dense(&data, num_rows).unwrap();
+        dtrain.set_labels(&labels).unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::RegQuantileError)
+            .quantile_alpha(vec![0.1, 0.9])
+            .build()
+            .unwrap();
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .tree_method(tree::TreeMethod::Hist)
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dtrain)
+            .boost_rounds(20)
+            .build()
+            .unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let (preds, shape) = booster.predict_2d(&dtrain).unwrap();
+        assert_eq!(shape, (num_rows, 2));
+        let mean_low = preds.chunks(2).map(|row| row[0]).sum::<f32>() / num_rows as f32;
+        let mean_high = preds.chunks(2).map(|row| row[1]).sum::<f32>() / num_rows as f32;
+        assert!(mean_low < mean_high);
+    }
+
+    #[test]
+    fn write_contribs() {
+        let dmat_train = read_train_matrix().unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat_train]).unwrap();
+        for i in 0..2 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = tmp_dir.path().join("contribs.csv");
+        booster.write_contribs(&dmat_train, &path, 1000).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), dmat_train.num_rows());
+        assert!(lines.iter().all(|line| line.split(',').count() == dmat_train.num_cols() + 1));
+
+        let (contribs, (_num_rows, num_cols)) = booster.predict_contributions(&dmat_train).unwrap();
+        let first_row: Vec<f32> = lines[0].split(',').map(|value| value.parse().unwrap()).collect();
+        assert_eq!(first_row, &contribs[..num_cols]);
+    }
+
+    #[test]
+    fn predict_to_csv() {
+        let dmat_train = read_train_matrix().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(2)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        let preds = booster.predict(&dmat_train).unwrap();
+
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = tmp_dir.path().join("preds.csv");
+        booster.predict_to_csv(&dmat_train, &path, true).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), dmat_train.num_rows());
+        for (i, line) in lines.iter().enumerate() {
+            let parts: Vec<&str> = line.split(',').collect();
+            assert_eq!(parts, vec![i.to_string(), preds[i].to_string()]);
+        }
+
+        booster.predict_to_csv(&dmat_train, &path, false).unwrap();
+        let values: Vec<f32> = fs::read_to_string(&path).unwrap()
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        assert_eq!(values, preds);
+    }
+
+    #[test]
+    fn predict_with_shape() {
+        let data = [1.0, 0.0,
+                    0.0, 1.0,
+                    1.0, 1.0,
+                    0.0, 0.0,
+                    1.0, 0.0,
+                    0.0, 1.0];
+        let mut dtrain = DMatrix::from_dense(&data, 6).unwrap();
+        dtrain.set_labels(&[0.0, 1.0, 2.0, 0.0, 1.0, 2.0]).unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::MultiSoftprob(3))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dtrain]).unwrap();
+        for i in 0..3 {
+            booster.update(&dtrain, i).expect("update failed");
+        }
+
+        let (preds, shape) = booster.predict_with_shape(&dtrain).unwrap();
+        assert_eq!(shape, vec![6, 3]);
+        assert_eq!(preds.len(), 18);
+        assert_eq!(preds, booster.predict(&dtrain).unwrap());
+    }
+
+    #[test]
+    fn predict_leaf() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(2)
+            .eta(1.0)
+            .build()
+            .unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+
+        let num_rounds = 15;
+        for i in 0..num_rounds {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let (_preds, shape) = booster.predict_leaf(&dmat_test).unwrap();
+        let num_samples = dmat_test.num_rows();
+        assert_eq!(shape, (num_samples, num_rounds as usize));
+    }
+
+    #[test]
+    fn predict_contributions() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(2)
+            .eta(1.0)
+            .build()
+            .unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+
+        let num_rounds = 5;
+        for i in 0..num_rounds {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let (_preds, shape) = booster.predict_contributions(&dmat_test).unwrap();
+        let num_samples = dmat_test.num_rows();
+        let num_features = dmat_train.num_cols();
+        assert_eq!(shape, (num_samples, num_features + 1));
+    }
+
+    #[test]
+    fn explain_row() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(2)
+            .eta(1.0)
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+        for i in 0..5 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let (_contribs, (_num_rows, num_contribs)) = booster.predict_contributions(&dmat_test).unwrap();
+        let num_features = booster.num_features().unwrap();
+        assert_eq!(num_contribs, num_features + 1);
+
+        let row = 3;
+        let (indptr, indices, data) = dmat_test.to_csr().unwrap();
+        let mut features = vec![f32::NAN; num_features];
+        for i in indptr[row]..indptr[row + 1] {
+            features[indices[i]] = data[i];
+        }
+
+        let check = |booster: &Booster| {
+            let (contribs, _shape) = booster.predict_contributions(&dmat_test).unwrap();
+            let explained = booster.explain_row(&features, f32::NAN).unwrap();
+            let expected = &contribs[row * num_contribs..(row + 1) * num_contribs];
+            assert_eq!(explained.len(), expected.len());
+            for (a, b) in explained.iter().zip(expected) {
+                assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+            }
+            explained
+        };
+        let all_rounds = check(&booster);
+
+        // e.g. after early stopping
+        booster.set_default_iteration_range((0, 2));
+        assert_ne!(check(&booster), all_rounds);
+    }
+
+    #[test]
+    fn predict_dense_missing() {
+        let dmat_train = read_train_matrix().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(3)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let num_rows = 10;
+        let num_features = booster.num_features().unwrap();
+        let (indptr, indices, data) = dmat_train.to_csr().unwrap();
+        let dense_with = |missing: f32| {
+            let mut dense = vec![missing; num_rows * num_features];
+            for row in 0..num_rows {
+                for i in indptr[row]..indptr[row + 1] {
+                    dense[row * num_features + indices[i]] = data[i];
+                }
+            }
+            dense
+        };
+
+        let preds_nan = booster.predict_dense(&dense_with(f32::NAN), num_rows, f32::NAN).unwrap();
+        let preds_sentinel = booster.predict_dense(&dense_with(-999.0), num_rows, -999.0).unwrap();
+        assert_eq!(preds_nan.len(), num_rows);
+        assert_eq!(preds_nan, preds_sentinel);
+        let preds = booster.predict(&dmat_train).unwrap();
+        for (a, b) in preds_nan.iter().zip(&preds) {
+            assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn predict_stream() {
+        let dmat_train = read_train_matrix().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(3)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let num_rows = 10;
+        let num_features = booster.num_features().unwrap();
+        let (indptr, indices, data) = dmat_train.to_csr().unwrap();
+        let rows = (0..num_rows).map(|row| {
+            let mut dense = vec![f32::NAN; num_features];
+            for i in indptr[row]..indptr[row + 1] {
+                dense[indices[i]] = data[i];
+            }
+            dense
+        });
+
+        let streamed: Vec<Vec<f32>> = booster.predict_stream(rows, f32::NAN)
+            .collect::<XGBResult<_>>()
+            .unwrap();
+        assert_eq!(streamed.len(), num_rows);
+        let preds = booster.predict(&dmat_train).unwrap();
+        for (row, expected) in streamed.iter().zip(&preds) {
+            assert_eq!(row.len(), 1);
+            assert!((row[0] - expected).abs() < 1e-6, "{} != {}", row[0], expected);
+        }
+
+        let mut bad_rows = booster.predict_stream(vec![vec![]].into_iter(), f32::NAN);
+        assert!(bad_rows.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn predict_interactions() {
+        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(2)
+            .eta(1.0)
+            .build()
+            .unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+
+        let num_rounds = 5;
+        for i in 0..num_rounds {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let (_preds, shape) = booster.predict_interactions(&dmat_test).unwrap();
+        let num_samples = dmat_test.num_rows();
+        let num_features = dmat_train.num_cols();
+        assert_eq!(shape, (num_samples, num_features + 1, num_features + 1));
+    }
+
+    #[test]
+    fn train_multiclass_label_out_of_range() {
+        let data = [1.0, 0.0,
+                    0.0, 1.0,
+                    1.0, 1.0,
+                    0.0, 0.0];
+        let train_err = |labels: &[f32]| {
+            let mut dtrain = DMatrix::from_dense(&data, 4).unwrap();
+            // unchecked, so NaN labels reach training
+            dtrain.set_labels_unchecked(labels).unwrap();
+
+            let learning_params = learning::LearningTaskParametersBuilder::default()
+                .objective(learning::Objective::MultiSoftprob(3))
+                .build()
+                .unwrap();
+            let booster_params = parameters::BoosterParametersBuilder::default()
+                .learning_params(learning_params)
+                .verbose(false)
+                .build()
+                .unwrap();
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .booster_params(booster_params)
+                .dtrain(&dtrain)
+                .boost_rounds(2)
+                .build()
+                .unwrap();
+            Booster::train(&training_params).err().expect("expected training to fail")
+        };
+
+        let err = train_err(&[0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(err, XGBError::LabelOutOfRange { max_label: 3.0, num_class: 3 });
+        assert!(err.to_string().contains("num_class=3"));
+
+        for labels in &[[0.0, -1.0, 2.0, 1.0], [0.0, 1.5, 2.0, 1.0], [0.0, f32::NAN, 2.0, 1.0]] {
+            match train_err(labels) {
+                XGBError::InvalidData(msg) => assert!(msg.contains(&labels[1].to_string()), "{}", msg),
+                err => panic!("unexpected error: {}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn train_early_stopping() {
+        use rand::{Rng, SeedableRng};
+
+        // random labels, so the model overfits and stops improving on the evaluation set
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let mut random_dmat = |num_rows: usize| {
+            let data: Vec<f32> = (0..num_rows * 5).map(|_| rng.gen()).collect();
+            let labels: Vec<f32> = (0..num_rows).map(|_| rng.gen_range(0..2) as f32).collect();
+            let mut dmat = DMatrix::from_dense(&data, num_rows).unwrap();
+            dmat.set_labels(&labels).unwrap();
+            dmat
+        };
+        let dtrain = random_dmat(200);
+        let dtest = random_dmat(200);
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(6)
+            .eta(1.0)
+            .build().unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build().unwrap();
+        let evaluation_sets = &[(&dtrain, "train"), (&dtest, "test")];
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dtrain)
+            .evaluation_sets(Some(evaluation_sets))
+            .boost_rounds(50)
+            .early_stopping_rounds(Some(3))
+            .build().unwrap();
+        let mut booster = Booster::train(&training_params).unwrap();
+
+        let best_iteration = booster.best_iteration().unwrap().expect("best iteration not set");
+        let num_rounds = booster.num_boosted_rounds().unwrap();
+        assert!(num_rounds < 50);
+        assert_eq!(num_rounds, best_iteration + 4);
+
+        let preds = booster.predict(&dtest).unwrap();
+        let (best_preds, _shape) = booster.predict_from_dmatrix(&dtest, PredictType::Value, (0, best_iteration + 1))
+            .unwrap();
+        assert_eq!(preds, best_preds);
+
+        // every prediction type uses the same rounds as predict
+        let margin = booster.predict_margin(&dtest).unwrap();
+        let (best_margin, _shape) = booster.predict_from_dmatrix(&dtest, PredictType::Margin, (0, best_iteration + 1))
+            .unwrap();
+        assert_eq!(margin, best_margin);
+        let (proba_margin, proba) = booster.predict_margin_and_proba(&dtest).unwrap();
+        assert_eq!(proba_margin, margin);
+        for (p, expected) in proba.iter().zip(&preds) {
+            assert!((p - expected).abs() < 1e-6);
+        }
+        let (_leaves, (_num_rows, num_trees)) = booster.predict_leaf(&dtest).unwrap();
+        assert_eq!(num_trees as u32, best_iteration + 1);
+        let (contribs, (_num_rows, num_cols)) = booster.predict_contributions(&dtest).unwrap();
+        for (row, m) in contribs.chunks(num_cols).zip(&margin) {
+            assert!((row.iter().sum::<f32>() - m).abs() < 1e-4);
+        }
+
+        booster.set_default_iteration_range((0, 0));
+        assert_ne!(booster.predict(&dtest).unwrap(), preds);
+        assert_ne!(booster.predict_margin(&dtest).unwrap(), margin);
+    }
+
+    #[test]
+    fn maximised_metrics() {
+        assert!(Booster::is_maximised_metric("valid-auc"));
+        assert!(Booster::is_maximised_metric("valid-ndcg@5"));
+        assert!(Booster::is_maximised_metric("ndcg-"));
+        assert!(Booster::is_maximised_metric("map@4-"));
+        assert!(Booster::is_maximised_metric("valid-ndcg@5-"));
+        assert!(!Booster::is_maximised_metric("valid-rmse"));
+        assert!(!Booster::is_maximised_metric("train-logloss"));
+    }
+
+    #[test]
+    fn train_disable_default_eval_metric() {
+        fn mean_margin(margin: &[f32], _dmat: &DMatrix) -> f32 {
+            margin.iter().sum::<f32>() / margin.len() as f32
+        }
+
+        let dtrain = read_train_matrix().unwrap();
+        let dtest = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+        let train = |learning_params: learning::LearningTaskParameters| {
+            let booster_params = parameters::BoosterParametersBuilder::default()
+                .learning_params(learning_params)
+                .verbose(false)
+                .build().unwrap();
+            let evaluation_sets = &[(&dtest, "valid")];
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .booster_params(booster_params)
+                .dtrain(&dtrain)
+                .evaluation_sets(Some(evaluation_sets))
+                .custom_evaluation_fn(Some(mean_margin))
+                .boost_rounds(2)
+                .build().unwrap();
+            let booster = Booster::train(&training_params).unwrap();
+            booster.eval_history().keys().cloned().collect::<Vec<String>>()
+        };
+
+        let default_metrics = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .build().unwrap();
+        assert_eq!(train(default_metrics), vec!["valid-custom", "valid-logloss"]);
+
+        let disabled = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .disable_default_eval_metric(true)
+            .build().unwrap();
+        assert_eq!(train(disabled), vec!["valid-custom"]);
+
+        let explicit = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::AUC]))
+            .disable_default_eval_metric(true)
+            .build().unwrap();
+        assert_eq!(train(explicit), vec!["valid-auc", "valid-custom"]);
+    }
+
+    #[test]
+    fn train_early_stopping_maximize() {
+        // custom metric which keeps increasing as the model gets more confident, then plateaus
+        fn capped_confidence(margin: &[f32], _dmat: &DMatrix) -> f32 {
+            let mean = margin.iter().map(|m| m.abs()).sum::<f32>() / margin.len() as f32;
+            mean.min(2.0)
+        }
+
+        let dtrain = read_train_matrix().unwrap();
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(2)
+            .eta(0.3)
+            .build().unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build().unwrap();
+        let evaluation_sets = &[(&dtrain, "train")];
+        let mut training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dtrain)
+            .evaluation_sets(Some(evaluation_sets))
+            .custom_evaluation_fn(Some(capped_confidence))
+            .boost_rounds(50)
+            .early_stopping_rounds(Some(3))
+            .early_stopping_maximize(Some(true))
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let history = &booster.eval_history()["train-custom"];
+        let best_iteration = booster.best_iteration().unwrap().expect("best iteration not set");
+        assert!(best_iteration > 0);
+        assert_eq!(history[best_iteration as usize], 2.0);
+        assert!(history[..best_iteration as usize].iter().all(|score| *score < 2.0));
+        assert_eq!(booster.num_boosted_rounds().unwrap(), best_iteration + 4);
+
+        // minimising the same metric stops as soon as it starts increasing
+        training_params.set_early_stopping_maximize(Some(false));
+        let booster = Booster::train(&training_params).unwrap();
+        assert_eq!(booster.best_iteration().unwrap(), Some(0));
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 4);
+    }
+
+    #[test]
+    fn to_forest() {
+        let dmat = read_train_matrix().unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .build().unwrap();
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(3)
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat)
+            .boost_rounds(5)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let forest = booster.to_forest().unwrap();
+        assert_eq!(forest.trees().len(), 5);
+
+        let preds = booster.predict(&dmat).unwrap();
+        let (indptr, indices, data) = dmat.to_csr().unwrap();
+        for (row, pred) in preds.iter().enumerate() {
+            let mut features = vec![f32::NAN; dmat.num_cols()];
+            for i in indptr[row]..indptr[row + 1] {
+                features[indices[i]] = data[i];
+            }
+            assert!((forest.predict(&features) - pred).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn train_with_split() {
+        let dmat = read_train_matrix().unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat)
+            .boost_rounds(4)
+            .build().unwrap();
+        let booster = Booster::train_with_split(&training_params, 0.2, 7).unwrap();
+
+        let history = booster.eval_history();
+        let names: Vec<&String> = history.keys().collect();
+        assert_eq!(names, vec!["train-logloss", "valid-logloss"]);
+        assert_eq!(history["valid-logloss"].len(), 4);
+        assert_eq!(history["train-logloss"].len(), 4);
+
+        assert!(Booster::train_with_split(&training_params, 1.0, 7).is_err());
     }
 
     #[test]
-    fn get_attribute_names() {
-        let mut booster = load_test_booster();
-        let attrs = booster.get_attribute_names().expect("Getting attributes failed");
-        assert_eq!(attrs, Vec::<String>::new());
+    fn parse_eval_string() {
+        let s = "[0]\ttrain-map@4-:0.5\ttrain-logloss:1.0\ttest-map@4-:0.25\ttest-logloss:0.75";
+        let mut metrics = IndexMap::new();
 
-        booster.set_attribute("foo", "bar").expect("Setting attribute failed");
-        booster.set_attribute("another", "another").expect("Setting attribute failed");
-        booster.set_attribute("4", "4").expect("Setting attribute failed");
-        booster.set_attribute("an even longer attribute name?", "").expect("Setting attribute failed");
+        let mut train_metrics = IndexMap::new();
+        train_metrics.insert("map@4-".to_owned(), 0.5);
+        train_metrics.insert("logloss".to_owned(), 1.0);
 
-        let mut expected = vec!["foo", "another", "4", "an even longer attribute name?"];
-        expected.sort();
-        let mut attrs = booster.get_attribute_names().expect("Getting attributes failed");
-        attrs.sort();
-        assert_eq!(attrs, expected);
+        let mut test_metrics = IndexMap::new();
+        test_metrics.insert("map@4-".to_owned(), 0.25);
+        test_metrics.insert("logloss".to_owned(), 0.75);
+
+        metrics.insert("train".to_owned(), train_metrics);
+        metrics.insert("test".to_owned(), test_metrics);
+        assert_eq!(Booster::parse_eval_string(s, &["train", "test"]), metrics);
+
+        let s = "[3]\ttrain-rmse:0.5\ttrain-mae:0.25\ttrain-2-rmse:0.75\ttrain-2-mae:0.125";
+        let result = Booster::parse_eval_string(s, &["train", "train-2"]);
+        assert_eq!(result["train"].len(), 2);
+        assert_eq!(result["train-2"]["rmse"], 0.75);
+        assert_eq!(result["train-2"]["mae"], 0.125);
     }
 
     #[test]
-    fn predict() {
-        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+    fn evaluate_sets() {
+        let dmat_train = read_train_matrix().unwrap();
         let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
 
-        let tree_params = tree::TreeBoosterParametersBuilder::default()
-            .max_depth(2)
-            .eta(1.0)
-            .build()
-            .unwrap();
         let learning_params = learning::LearningTaskParametersBuilder::default()
             .objective(learning::Objective::BinaryLogistic)
-            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::MAPCutNegative(4),
-                                                         learning::EvaluationMetric::LogLoss,
-                                                         learning::EvaluationMetric::BinaryErrorRate(0.5)]))
-            .build()
-            .unwrap();
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss,
+                                                         learning::EvaluationMetric::AUC]))
+            .build().unwrap();
         let params = parameters::BoosterParametersBuilder::default()
-            .booster_type(parameters::BoosterType::Tree(tree_params))
             .learning_params(learning_params)
             .verbose(false)
-            .build()
-            .unwrap();
+            .build().unwrap();
         let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
-
-        for i in 0..10 {
+        for i in 0..3 {
             booster.update(&dmat_train, i).expect("update failed");
         }
 
-        let train_metrics = booster.evaluate(&dmat_train).unwrap();
-        assert_eq!(*train_metrics.get("logloss").unwrap(), 0.006634271);
-        assert_eq!(*train_metrics.get("map@4-").unwrap(), 0.0012738854);
+        let evals = [(&dmat_train, "train"), (&dmat_test, "test")];
+        let nested = booster.evaluate_sets(&evals).unwrap();
+        assert_eq!(nested.len(), 2);
+        assert_eq!(nested["train"].len(), 2);
+        assert_eq!(nested["test"].len(), 2);
+        assert_eq!(nested["test"]["logloss"], booster.evaluate(&dmat_test).unwrap()["logloss"]);
 
-        let test_metrics = booster.evaluate(&dmat_test).unwrap();
-        assert_eq!(*test_metrics.get("logloss").unwrap(), 0.006919953);
-        assert_eq!(*test_metrics.get("map@4-").unwrap(), 0.005154639);
+        let flat = booster.evaluate_sets_flat(&evals).unwrap();
+        assert_eq!(flat.len(), 4);
+        assert_eq!(flat["train-auc"], nested["train"]["auc"]);
+    }
 
-        let v = booster.predict(&dmat_test).unwrap();
-        assert_eq!(v.len(), dmat_test.num_rows());
+    #[test]
+    fn eval_history_multiple_metrics() {
+        let dmat_train = read_train_matrix().unwrap();
+        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
 
-        // first 10 predictions
-        let expected_start = [0.0050151693,
-                              0.9884467,
-                              0.0050151693,
-                              0.0050151693,
-                              0.026636455,
-                              0.11789363,
-                              0.9884467,
-                              0.01231471,
-                              0.9884467,
-                              0.00013656063];
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::RegLinear)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::RMSE,
+                                                         learning::EvaluationMetric::MAE]))
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build().unwrap();
+        let evaluation_sets = &[(&dmat_test, "valid")];
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .evaluation_sets(Some(evaluation_sets))
+            .boost_rounds(3)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
 
-        // last 10 predictions
-        let expected_end = [0.002520344,
-                            0.00060917926,
-                            0.99881005,
-                            0.00060917926,
-                            0.00060917926,
-                            0.00060917926,
-                            0.00060917926,
-                            0.9981102,
-                            0.002855195,
-                            0.9981102];
-        let eps = 1e-6;
+        let history = booster.eval_history();
+        let names: Vec<&String> = history.keys().collect();
+        assert_eq!(names, vec!["valid-mae", "valid-rmse"]);
+        assert_eq!(history["valid-rmse"].len(), 3);
+        assert_eq!(history["valid-mae"].len(), 3);
+        assert_ne!(history["valid-rmse"], history["valid-mae"]);
+    }
 
-        for (pred, expected) in v.iter().zip(&expected_start) {
-            println!("predictions={}, expected={}", pred, expected);
-            assert!(pred - expected < eps);
-        }
+    #[test]
+    fn train_round_timings() {
+        let dmat_train = read_train_matrix().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(4)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
 
-        for (pred, expected) in v[v.len()-10..].iter().zip(&expected_end) {
-            println!("predictions={}, expected={}", pred, expected);
-            assert!(pred - expected < eps);
-        }
+        let timings = booster.round_timings();
+        assert_eq!(timings.len(), 4);
+        assert!(timings.iter().all(|timing| *timing > Duration::from_secs(0)));
+
+        let untrained = Booster::new(&parameters::BoosterParameters::default()).unwrap();
+        assert!(untrained.round_timings().is_empty());
     }
 
     #[test]
-    fn predict_leaf() {
+    fn train_progress_reports() {
+        use std::cell::RefCell;
+
         let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
         let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
 
-        let tree_params = tree::TreeBoosterParametersBuilder::default()
-            .max_depth(2)
-            .eta(1.0)
-            .build()
-            .unwrap();
         let learning_params = learning::LearningTaskParametersBuilder::default()
             .objective(learning::Objective::BinaryLogistic)
-            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
-            .build()
-            .unwrap();
-        let params = parameters::BoosterParametersBuilder::default()
-            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss,
+                                                         learning::EvaluationMetric::AUC]))
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
             .learning_params(learning_params)
             .verbose(false)
-            .build()
-            .unwrap();
-        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+            .build().unwrap();
 
-        let num_rounds = 15;
-        for i in 0..num_rounds {
-            booster.update(&dmat_train, i).expect("update failed");
+        let reports = RefCell::new(Vec::new());
+        let progress_fn = |report: &IterationReport| reports.borrow_mut().push(report.clone());
+        let evaluation_sets = [(&dmat_test, "test")];
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(4)
+            .evaluation_sets(Some(&evaluation_sets[..]))
+            .progress_fn(Some(&progress_fn as &dyn Fn(&IterationReport)))
+            .build().unwrap();
+        Booster::train(&training_params).unwrap();
+
+        let reports = reports.into_inner();
+        assert_eq!(reports.len(), 4);
+        for (i, report) in reports.iter().enumerate() {
+            assert_eq!(report.round, i as u32);
+            let names: Vec<&str> = report.metrics.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, vec!["test-logloss", "test-auc"]);
         }
+    }
 
-        let (_preds, shape) = booster.predict_leaf(&dmat_test).unwrap();
-        let num_samples = dmat_test.num_rows();
-        assert_eq!(shape, (num_samples, num_rounds as usize));
+    #[test]
+    fn train_cancel() {
+        use std::sync::Arc;
+        use std::sync::atomic::AtomicBool;
+
+        let dmat_train = read_train_matrix().unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let progress_fn = |report: &IterationReport| {
+            if report.round == 1 {
+                cancel.store(true, Ordering::SeqCst);
+            }
+        };
+
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(10)
+            .progress_fn(Some(&progress_fn as &dyn Fn(&IterationReport)))
+            .cancel(Some(cancel.clone()))
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 2);
     }
 
     #[test]
-    fn predict_contributions() {
+    fn dump_trees() {
         let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
-        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
 
         let tree_params = tree::TreeBoosterParametersBuilder::default()
             .max_depth(2)
             .eta(1.0)
-            .build()
-            .unwrap();
-        let learning_params = learning::LearningTaskParametersBuilder::default()
-            .objective(learning::Objective::BinaryLogistic)
-            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
-            .build()
-            .unwrap();
-        let params = parameters::BoosterParametersBuilder::default()
+            .build().unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
             .booster_type(parameters::BoosterType::Tree(tree_params))
-            .learning_params(learning_params)
             .verbose(false)
-            .build()
-            .unwrap();
-        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat_train)
+            .boost_rounds(10)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 10);
 
-        let num_rounds = 5;
-        for i in 0..num_rounds {
-            booster.update(&dmat_train, i).expect("update failed");
-        }
+        let eager = booster.dump_model_json(true).unwrap();
+        assert_eq!(eager.len(), 10);
 
-        let (_preds, shape) = booster.predict_contributions(&dmat_test).unwrap();
-        let num_samples = dmat_test.num_rows();
-        let num_features = dmat_train.num_cols();
-        assert_eq!(shape, (num_samples, num_features + 1));
+        let lazy: XGBResult<Vec<String>> = booster.dump_trees(true).collect();
+        assert_eq!(lazy.unwrap(), eager);
     }
 
     #[test]
-    fn predict_interactions() {
-        let dmat_train = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
-        let dmat_test = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.test").unwrap();
+    fn linear_weights() {
+        let num_rows = 100;
+        let num_cols = 3;
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        for i in 0..num_rows {
+            let row = [(i % 10 + 1) as f32, (i % 7 + 1) as f32, (i % 3 + 1) as f32];
+            labels.push(2.0 * row[0] - row[1] + 0.5 * row[2] + 3.0);
+            data.extend_from_slice(&row);
+        }
+        let mut dtrain = DMatrix::from_dense(&data, num_rows).unwrap();
+        dtrain.set_labels(&labels).unwrap();
 
-        let tree_params = tree::TreeBoosterParametersBuilder::default()
-            .max_depth(2)
-            .eta(1.0)
+        let linear_params = linear::LinearBoosterParametersBuilder::default()
+            .updater(linear::LinearUpdate::CoordDescent)
             .build()
             .unwrap();
-        let learning_params = learning::LearningTaskParametersBuilder::default()
-            .objective(learning::Objective::BinaryLogistic)
-            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Linear(linear_params))
+            .verbose(false)
             .build()
             .unwrap();
-        let params = parameters::BoosterParametersBuilder::default()
-            .booster_type(parameters::BoosterType::Tree(tree_params))
-            .learning_params(learning_params)
-            .verbose(false)
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dtrain)
+            .boost_rounds(10)
             .build()
             .unwrap();
-        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+        let booster = Booster::train(&training_params).unwrap();
 
-        let num_rounds = 5;
-        for i in 0..num_rounds {
-            booster.update(&dmat_train, i).expect("update failed");
+        let (weights, bias) = booster.linear_weights().unwrap();
+        assert_eq!(weights.len(), num_cols);
+        let base_score = booster.base_score().unwrap();
+        let margins = booster.predict_margin(&dtrain).unwrap();
+        for (row, margin) in data.chunks(num_cols).zip(&margins) {
+            let dot: f32 = row.iter().zip(&weights).map(|(x, w)| x * w).sum();
+            assert!((base_score + bias + dot - margin).abs() < 1e-3);
         }
 
-        let (_preds, shape) = booster.predict_interactions(&dmat_test).unwrap();
-        let num_samples = dmat_test.num_rows();
-        let num_features = dmat_train.num_cols();
-        assert_eq!(shape, (num_samples, num_features + 1, num_features + 1));
+        let tree_booster = load_test_booster();
+        assert!(tree_booster.linear_weights().is_err());
     }
 
     #[test]
-    fn parse_eval_string() {
-        let s = "[0]\ttrain-map@4-:0.5\ttrain-logloss:1.0\ttest-map@4-:0.25\ttest-logloss:0.75";
-        let mut metrics = IndexMap::new();
+    fn used_features() {
+        // feature 0 determines the label, feature 1 is noise and feature 2 is constant
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        for i in 0..100 {
+            let x = (i % 2) as f32;
+            data.extend_from_slice(&[x, ((i * 7) % 5) as f32, 1.0]);
+            labels.push(x);
+        }
+        let mut dtrain = DMatrix::from_dense(&data, 100).unwrap();
+        dtrain.set_labels(&labels).unwrap();
 
-        let mut train_metrics = IndexMap::new();
-        train_metrics.insert("map@4-".to_owned(), 0.5);
-        train_metrics.insert("logloss".to_owned(), 1.0);
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dtrain)
+            .boost_rounds(5)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
 
-        let mut test_metrics = IndexMap::new();
-        test_metrics.insert("map@4-".to_owned(), 0.25);
-        test_metrics.insert("logloss".to_owned(), 0.75);
+        let used = booster.used_features().unwrap();
+        assert!(used.contains(&0));
+        assert!(!used.contains(&2));
+        let mut sorted = used.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(used, sorted);
+    }
 
-        metrics.insert("train".to_owned(), train_metrics);
-        metrics.insert("test".to_owned(), test_metrics);
-        assert_eq!(Booster::parse_eval_string(s, &["train", "test"]), metrics);
+    #[test]
+    fn feature_weights_sampling() {
+        // feature 0 determines the label, features 1 and 2 are noise
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        for i in 0..100 {
+            let x = (i % 2) as f32;
+            data.extend_from_slice(&[x, ((i * 7) % 5) as f32, ((i * 3) % 7) as f32]);
+            labels.push(x);
+        }
+
+        let train = |feature_weights: &[f32]| {
+            let mut dtrain = DMatrix::from_dense_with_missing(&data, 100, f32::NAN).unwrap();
+            dtrain.set_labels(&labels).unwrap();
+            dtrain.set_feature_weights(feature_weights).unwrap();
+
+            let tree_params = tree::TreeBoosterParametersBuilder::default()
+                .colsample_bytree(0.5)
+                .build().unwrap();
+            let booster_params = parameters::BoosterParametersBuilder::default()
+                .booster_type(parameters::BoosterType::Tree(tree_params))
+                .verbose(false)
+                .build().unwrap();
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .booster_params(booster_params)
+                .dtrain(&dtrain)
+                .boost_rounds(10)
+                .build().unwrap();
+            Booster::train(&training_params).unwrap()
+        };
+
+        let uniform = train(&[1.0, 1.0, 1.0]);
+        assert!(uniform.used_features().unwrap().contains(&0));
+
+        // each tree samples a single column, so a zero weighted one is never picked over the others
+        let weighted = train(&[0.0, 1.0, 1.0]);
+        assert!(!weighted.used_features().unwrap().contains(&0));
+    }
+
+    #[test]
+    fn save_and_load_feature_names() {
+        let mut dmat = read_train_matrix().unwrap();
+        let names: Vec<String> = (0..dmat.num_cols()).map(|i| format!("feature_{}", i)).collect();
+        dmat.set_feature_names(&names).unwrap();
+
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build().unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .booster_params(booster_params)
+            .dtrain(&dmat)
+            .boost_rounds(2)
+            .build().unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        assert!(booster.dump_model(false, None).unwrap().contains("[feature_"));
+
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        for file_name in &["model.bin", "model.json"] {
+            let path = tmp_dir.path().join(file_name);
+            booster.save(&path).unwrap();
+            let loaded = Booster::load(&path).unwrap();
+            assert_eq!(loaded.feature_names().unwrap(), names);
+            assert!(loaded.dump_model(false, None).unwrap().contains("[feature_"));
+        }
     }
 
     #[test]
@@ -1,17 +1,30 @@
-use std::{slice, ffi, ptr, path::Path};
-use libc::{c_uint, c_float};
+use std::{fmt, slice, ffi, ptr, path::Path};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::panic::{self, AssertUnwindSafe};
+use libc::{c_int, c_uint, c_float};
 use std::os::unix::ffi::OsStrExt;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 
 use xgboost_sys;
+use rand::{self, Rng, SeedableRng};
+use rand::rngs::StdRng;
+#[cfg(feature = "ndarray")]
+use ndarray::{Array2, ArrayView1};
+#[cfg(feature = "sprs")]
+use sprs::CsMat;
 
-use super::{XGBResult, XGBError};
+use super::{XGBResult, XGBError, Booster, FeatureType, LabelEncoder};
+use super::array_interface;
+use parameters::{BoosterParametersBuilder, BoosterType};
+use parameters::tree::{TreeBoosterParametersBuilder, TreeMethod};
 
 static KEY_GROUP_PTR: &'static str = "group_ptr";
 static KEY_GROUP: &'static str = "group";
 static KEY_LABEL: &'static str = "label";
 static KEY_WEIGHT: &'static str = "weight";
 static KEY_BASE_MARGIN: &'static str = "base_margin";
+static KEY_FEATURE_WEIGHTS: &'static str = "feature_weights";
 
 /// Data matrix used throughout XGBoost for training/predicting [`Booster`](struct.Booster.html) models.
 ///
@@ -72,6 +85,10 @@ pub struct DMatrix {
     pub(super) handle: xgboost_sys::DMatrixHandle,
     num_rows: usize,
     num_cols: usize,
+    // reused by set_labels_iter, so repeatedly setting labels from iterators doesn't allocate each time
+    label_buffer: Vec<f32>,
+    // XGBoost has no field for arbitrary per row data, so row ids are only kept on the Rust side
+    row_ids: Option<Vec<u64>>,
 }
 
 impl DMatrix {
@@ -88,7 +105,7 @@ impl DMatrix {
         let num_cols = out as usize;
 
         info!("Loaded DMatrix with shape: {}x{}", num_rows, num_cols);
-        Ok(DMatrix { handle, num_rows, num_cols })
+        Ok(DMatrix { handle, num_rows, num_cols, label_buffer: Vec::new(), row_ids: None })
     }
 
     /// Create a new `DMatrix` from dense array in row-major order.
@@ -107,16 +124,63 @@ impl DMatrix {
     /// let num_rows = 3;
     /// let dmat = DMatrix::from_dense(data, num_rows).unwrap();
     /// ```
+    ///
+    /// Values of `0.0` are treated as missing, see
+    /// [`from_dense_with_missing`](struct.DMatrix.html#method.from_dense_with_missing) to use a different value.
     pub fn from_dense(data: &[f32], num_rows: usize) -> XGBResult<Self> {
+        DMatrix::from_dense_with_missing(data, num_rows, 0.0)
+    }
+
+    /// Create a new `DMatrix` from dense array in row-major order, treating values equal to `missing` (which
+    /// may be NaN) as missing.
+    ///
+    /// Returns an `XGBError::InvalidParameter` error if `num_rows` is 0.
+    pub fn from_dense_with_missing(data: &[f32], num_rows: usize, missing: f32) -> XGBResult<Self> {
+        let num_cols = data.len().checked_div(num_rows).unwrap_or(0);
+        DMatrix::from_dense_with_shape(data, num_rows, num_cols, missing)
+    }
+
+    /// Create a new `DMatrix` from the first `num_rows * num_cols` values of a dense array in row-major order.
+    fn from_dense_with_shape(data: &[f32], num_rows: usize, num_cols: usize, missing: f32) -> XGBResult<Self> {
+        if num_rows == 0 {
+            return Err(XGBError::InvalidParameter("num_rows must be greater than 0".to_owned()));
+        }
+        assert!(data.len() >= num_rows * num_cols);
+
         let mut handle = ptr::null_mut();
         xgb_call!(xgboost_sys::XGDMatrixCreateFromMat(data.as_ptr(),
                                                       num_rows as xgboost_sys::bst_ulong,
-                                                      (data.len() / num_rows) as xgboost_sys::bst_ulong,
-                                                      0.0, // TODO: can values be missing here?
+                                                      num_cols as xgboost_sys::bst_ulong,
+                                                      missing,
                                                       &mut handle))?;
         Ok(DMatrix::new(handle)?)
     }
 
+    /// Create a new `DMatrix` from dense array in row-major order, along with its labels and optional
+    /// weights, ready for training.
+    ///
+    /// Returns an `XGBError::DimensionMismatch` error unless `data` has `num_rows * num_cols` values, and
+    /// `labels` (and `weights`, if given) have one value per row, or an `XGBError::InvalidParameter` error if
+    /// `num_rows` is 0.
+    pub fn from_dense_labeled(data: &[f32], num_rows: usize, num_cols: usize, missing: f32, labels: &[f32],
+                              weights: Option<&[f32]>) -> XGBResult<Self> {
+        if data.len() != num_rows * num_cols {
+            return Err(XGBError::DimensionMismatch { expected: num_rows * num_cols, got: data.len() });
+        }
+        if let Some(weights) = weights {
+            if weights.len() != num_rows {
+                return Err(XGBError::DimensionMismatch { expected: num_rows, got: weights.len() });
+            }
+        }
+
+        let mut dmat = DMatrix::from_dense_with_shape(data, num_rows, num_cols, missing)?;
+        dmat.set_labels(labels)?;
+        if let Some(weights) = weights {
+            dmat.set_weights(weights)?;
+        }
+        Ok(dmat)
+    }
+
     /// Create a new `DMatrix` from a sparse
     /// [CSR](https://en.wikipedia.org/wiki/Sparse_matrix#Compressed_sparse_row_(CSR,_CRS_or_Yale_format)) matrix.
     ///
@@ -125,8 +189,14 @@ impl DMatrix {
     /// `data[indptr[i]:indptr[i+1]`.
     ///
     /// If `num_cols` is set to None, number of columns will be inferred from given data.
+    ///
+    /// Rows without any entries are allowed (e.g. `indptr = [0, 0, 0]` describes 2 empty rows), but
+    /// an `indptr` describing no rows at all returns an `XGBError::InvalidCsr` error.
     pub fn from_csr(indptr: &[usize], indices: &[usize], data: &[f32], num_cols: Option<usize>) -> XGBResult<Self> {
         assert_eq!(indices.len(), data.len());
+        if indptr.len() < 2 {
+            return Err(XGBError::InvalidCsr("indptr must describe at least one row".to_owned()));
+        }
         let mut handle = ptr::null_mut();
         let indices: Vec<u32> = indices.iter().map(|x| *x as u32).collect();
         let num_cols = num_cols.unwrap_or(0); // infer from data if 0
@@ -140,6 +210,129 @@ impl DMatrix {
         Ok(DMatrix::new(handle)?)
     }
 
+    /// Create a new `DMatrix` from batches of rows pulled from `next_batch` until it returns `None`.
+    ///
+    /// Lets rows come from any source (e.g. a custom data loader) without collecting them into a single
+    /// CSR matrix first. Labels and weights are kept if given for every batch. The number of columns is
+    /// inferred from the highest index seen.
+    ///
+    /// Returns an error if a batch is inconsistently sized. Panics in `next_batch` are passed on once XGBoost
+    /// has stopped reading.
+    pub fn from_batches<F: FnMut() -> Option<Batch>>(mut next_batch: F) -> XGBResult<Self> {
+        let mut iter = BatchIter { next_batch: &mut next_batch, error: None, panic: None };
+        let mut handle = ptr::null_mut();
+        let result = xgb_call!(xgboost_sys::XGDMatrixCreateFromDataIter(
+            &mut iter as *mut BatchIter as xgboost_sys::DataIterHandle,
+            Some(next_batch_callback),
+            ptr::null(),
+            &mut handle));
+
+        if let Some(panic) = iter.panic {
+            panic::resume_unwind(panic);
+        }
+        if let Some(err) = iter.error {
+            if !handle.is_null() {
+                xgb_call!(xgboost_sys::XGDMatrixFree(handle))?;
+            }
+            return Err(err);
+        }
+        result?;
+        DMatrix::new(handle)
+    }
+
+    /// Create a new `DMatrix` from a sparse CSR matrix, as with [`from_csr`](struct.DMatrix.html#method.from_csr),
+    /// after checking that the column indices within each row are sorted and unique.
+    ///
+    /// XGBoost's behaviour is undefined for rows with duplicate indices. If `sum_duplicates` is set, such rows
+    /// are sorted and their duplicate entries summed into one. Otherwise an `XGBError::InvalidCsr` error is
+    /// returned for the first row with unsorted or duplicate indices.
+    pub fn from_csr_validated(indptr: &[usize], indices: &[usize], data: &[f32], num_cols: Option<usize>,
+                              sum_duplicates: bool) -> XGBResult<Self> {
+        if indices.len() != data.len() {
+            return Err(XGBError::DimensionMismatch { expected: indices.len(), got: data.len() });
+        }
+
+        let mut valid_indptr = Vec::with_capacity(indptr.len());
+        let mut valid_indices = Vec::with_capacity(indices.len());
+        let mut valid_data = Vec::with_capacity(data.len());
+        valid_indptr.push(0);
+        for (row, bounds) in indptr.windows(2).enumerate() {
+            if bounds[0] > bounds[1] || bounds[1] > data.len() {
+                return Err(XGBError::InvalidCsr(format!("indptr for row {} is out of bounds", row)));
+            }
+            let mut entries: Vec<(usize, f32)> = (bounds[0]..bounds[1]).map(|i| (indices[i], data[i])).collect();
+            if entries.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+                if !sum_duplicates {
+                    return Err(XGBError::InvalidCsr(format!("row {} has unsorted or duplicate column indices", row)));
+                }
+                entries.sort_by_key(|&(index, _)| index);
+            }
+            for (index, value) in entries {
+                if valid_indices.len() > valid_indptr[row] && *valid_indices.last().unwrap() == index {
+                    *valid_data.last_mut().unwrap() += value;
+                } else {
+                    valid_indices.push(index);
+                    valid_data.push(value);
+                }
+            }
+            valid_indptr.push(valid_data.len());
+        }
+        DMatrix::from_csr(&valid_indptr, &valid_indices, &valid_data, num_cols)
+    }
+
+    /// Create a new `DMatrix` from sparse rows, each mapping feature indices to values.
+    ///
+    /// Indices within each row are sorted before building the matrix. If `skip_zeros` is set, zero values are
+    /// left out (so treated as missing) rather than stored.
+    ///
+    /// If `num_cols` is set to None, number of columns will be inferred from given data.
+    pub fn from_sparse_rows(rows: &[HashMap<u32, f32>], num_cols: Option<usize>, skip_zeros: bool)
+                            -> XGBResult<Self> {
+        let mut indptr = Vec::with_capacity(rows.len() + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(0);
+        for row in rows {
+            let mut entries: Vec<(u32, f32)> = row.iter()
+                .filter(|&(_, value)| !(skip_zeros && *value == 0.0))
+                .map(|(index, value)| (*index, *value))
+                .collect();
+            entries.sort_unstable_by_key(|&(index, _)| index);
+            for (index, value) in entries {
+                indices.push(index as usize);
+                data.push(value);
+            }
+            indptr.push(data.len());
+        }
+        DMatrix::from_csr(&indptr, &indices, &data, num_cols)
+    }
+
+    /// Create a new `DMatrix` from dense rows of equal length, leaving out (so treating as missing) every value
+    /// for which `is_missing` returns true, as well as NaN.
+    ///
+    /// Useful when missing values aren't marked by a single sentinel, e.g. `|value| value < 0.0` to treat every
+    /// negative value as missing. Returns an `XGBError::DimensionMismatch` error if rows differ in length.
+    pub fn from_rows_with<F: Fn(f32) -> bool>(rows: &[Vec<f32>], is_missing: F) -> XGBResult<Self> {
+        let num_cols = rows.first().map_or(0, |row| row.len());
+        let mut indptr = Vec::with_capacity(rows.len() + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(0);
+        for row in rows {
+            if row.len() != num_cols {
+                return Err(XGBError::DimensionMismatch { expected: num_cols, got: row.len() });
+            }
+            for (index, value) in row.iter().enumerate() {
+                if !(value.is_nan() || is_missing(*value)) {
+                    indices.push(index);
+                    data.push(*value);
+                }
+            }
+            indptr.push(data.len());
+        }
+        DMatrix::from_csr(&indptr, &indices, &data, Some(num_cols))
+    }
+
     /// Create a new `DMatrix` from a sparse
     /// [CSC](https://en.wikipedia.org/wiki/Sparse_matrix#Compressed_sparse_column_(CSC_or_CCS))) matrix.
     ///
@@ -148,8 +341,14 @@ impl DMatrix {
     /// `data[indptr[i]:indptr[i+1]`.
     ///
     /// If `num_rows` is set to None, number of rows will be inferred from given data.
+    ///
+    /// Columns without any entries are allowed, but an `indptr` describing no columns at all returns an
+    /// `XGBError::InvalidCsr` error.
     pub fn from_csc(indptr: &[usize], indices: &[usize], data: &[f32], num_rows: Option<usize>) -> XGBResult<Self> {
         assert_eq!(indices.len(), data.len());
+        if indptr.len() < 2 {
+            return Err(XGBError::InvalidCsr("indptr must describe at least one column".to_owned()));
+        }
         let mut handle = ptr::null_mut();
         let indices: Vec<u32> = indices.iter().map(|x| *x as u32).collect();
         let num_rows = num_rows.unwrap_or(0); // infer from data if 0
@@ -185,20 +384,229 @@ impl DMatrix {
     /// 1 9:1 11:0.375 15:1
     /// 0 1:0 8:0.22 11:1
     /// ```
+    ///
+    /// # Memory mapping
+    ///
+    /// XGBoost's binary `DMatrix` loader always reads the whole file into memory, and has no URI parameter
+    /// for memory mapping it instead, so there's no memory-mapped equivalent of this function. To reduce
+    /// memory usage for large datasets, use XGBoost's external memory support instead, e.g. by loading a
+    /// LIBSVM file with a `#<cache prefix>` suffix on its path.
     pub fn load<P: AsRef<Path>>(path: P) -> XGBResult<Self> {
+        DMatrix::load_with_silent(path, true)
+    }
+
+    /// Create a new `DMatrix` from given file, as with [`load`](struct.DMatrix.html#method.load).
+    ///
+    /// If `silent` is false, XGBoost logs diagnostics while loading (e.g. the number of rows and columns
+    /// read), which can help when debugging malformed files.
+    pub fn load_with_silent<P: AsRef<Path>>(path: P, silent: bool) -> XGBResult<Self> {
         debug!("Loading DMatrix from: {}", path.as_ref().display());
         let mut handle = ptr::null_mut();
-        let fname = ffi::CString::new(path.as_ref().as_os_str().as_bytes()).unwrap();
-        let silent = true;
+        let fname = ffi::CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|_| XGBError::InvalidPath(path.as_ref().to_path_buf()))?;
         xgb_call!(xgboost_sys::XGDMatrixCreateFromFile(fname.as_ptr(), silent as i32, &mut handle))?;
         Ok(DMatrix::new(handle)?)
     }
 
+    /// Create a new `DMatrix` from given file, treating any stored value equal to `missing` as missing.
+    ///
+    /// Useful for e.g. LIBSVM files which explicitly store some sentinel value for missing entries. See
+    /// [`load`](struct.DMatrix.html#method.load) for supported formats.
+    ///
+    /// Entries not stored in the file are always treated as missing. If `missing` is NaN, this is the same as
+    /// `load`, otherwise the loaded matrix is rebuilt without the matching entries (keeping labels, weights,
+    /// base margins and groups).
+    pub fn load_with_missing<P: AsRef<Path>>(path: P, missing: f32) -> XGBResult<Self> {
+        let dmat = DMatrix::load(path)?;
+        if missing.is_nan() {
+            return Ok(dmat);
+        }
+
+        let (indptr, indices, data) = dmat.to_csr()?;
+        let mut filtered_indptr = Vec::with_capacity(indptr.len());
+        let mut filtered_indices = Vec::with_capacity(indices.len());
+        let mut filtered_data = Vec::with_capacity(data.len());
+        filtered_indptr.push(0);
+        for row in indptr.windows(2) {
+            for i in row[0]..row[1] {
+                if data[i] != missing {
+                    filtered_indices.push(indices[i]);
+                    filtered_data.push(data[i]);
+                }
+            }
+            filtered_indptr.push(filtered_data.len());
+        }
+
+        let mut filtered = DMatrix::from_csr(&filtered_indptr, &filtered_indices, &filtered_data,
+                                             Some(dmat.num_cols()))?;
+        filtered.copy_info_from(&dmat)?;
+        Ok(filtered)
+    }
+
+    /// Create a new `DMatrix` from LIBSVM formatted text (as described in [`load`](struct.DMatrix.html#method.load)),
+    /// read incrementally from `reader`.
+    ///
+    /// Useful for data which isn't in a plain file, e.g. a decompressed stream. Each line holds a label
+    /// followed by `index:value` pairs, with an optional `qid:<id>` after the label to set ranking groups
+    /// (rows of a group must be contiguous). Blank lines and `#` comments are skipped.
+    ///
+    /// If `num_cols` is set to None, number of columns will be inferred from the highest index seen.
+    pub fn from_libsvm_reader<R: BufRead>(reader: R, num_cols: Option<usize>) -> XGBResult<Self> {
+        let mut indptr = vec![0];
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        let mut qids: Vec<u64> = Vec::new();
+
+        for (line_num, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| XGBError::new(format!("Unable to read LIBSVM data: {}", err)))?;
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |token: &str| {
+                XGBError::InvalidData(format!("Unable to parse '{}' on line {} of LIBSVM data", token, line_num + 1))
+            };
+
+            let mut tokens = line.split_whitespace();
+            let label = tokens.next().unwrap();
+            labels.push(label.parse::<f32>().map_err(|_| invalid(label))?);
+            for token in tokens {
+                let mut parts = token.splitn(2, ':');
+                let (key, value) = match (parts.next(), parts.next()) {
+                    (Some(key), Some(value)) => (key, value),
+                    _                        => return Err(invalid(token)),
+                };
+                if key == "qid" {
+                    qids.push(value.parse().map_err(|_| invalid(token))?);
+                    continue;
+                }
+                indices.push(key.parse::<usize>().map_err(|_| invalid(token))?);
+                data.push(value.parse::<f32>().map_err(|_| invalid(token))?);
+            }
+            indptr.push(data.len());
+        }
+
+        if !qids.is_empty() && qids.len() != labels.len() {
+            return Err(XGBError::InvalidData("qid must be set on every line of LIBSVM data, or none".to_owned()));
+        }
+
+        let mut dmat = DMatrix::from_csr(&indptr, &indices, &data, num_cols)?;
+        dmat.set_labels(&labels)?;
+        if !qids.is_empty() {
+            let mut group_sizes: Vec<u32> = Vec::new();
+            for (i, qid) in qids.iter().enumerate() {
+                if i > 0 && qids[i - 1] == *qid {
+                    *group_sizes.last_mut().unwrap() += 1;
+                } else {
+                    group_sizes.push(1);
+                }
+            }
+            dmat.set_group_sizes(&group_sizes)?;
+        }
+        Ok(dmat)
+    }
+
+    /// Create a new `DMatrix` by loading each of the given files and stacking their rows, in order.
+    ///
+    /// See [`load`](struct.DMatrix.html#method.load) for supported formats. As LIBSVM files don't record their
+    /// number of columns, each file is assumed to have as many columns as its highest feature index seen, so
+    /// files with different numbers of columns are an error unless `num_cols` is given explicitly.
+    pub fn load_many<P: AsRef<Path>>(paths: &[P], num_cols: Option<usize>) -> XGBResult<Self> {
+        let dmats = paths.iter()
+            .map(DMatrix::load)
+            .collect::<XGBResult<Vec<DMatrix>>>()?;
+        let dmats: Vec<&DMatrix> = dmats.iter().collect();
+        match num_cols {
+            Some(num_cols) => DMatrix::concat_rows(&dmats, num_cols),
+            None           => DMatrix::vstack(&dmats),
+        }
+    }
+
+    /// Create a new `DMatrix` by stacking the rows of given matrices, in order.
+    ///
+    /// All matrices must have the same number of columns. Labels, weights, base margins, groups and row ids are
+    /// kept if every matrix has them set.
+    pub fn vstack(dmats: &[&DMatrix]) -> XGBResult<Self> {
+        let num_cols = match dmats.first() {
+            Some(dmat) => dmat.num_cols(),
+            None       => return Err(XGBError::new("Cannot stack an empty list of matrices")),
+        };
+        if let Some(dmat) = dmats.iter().find(|dmat| dmat.num_cols() != num_cols) {
+            return Err(XGBError::DimensionMismatch { expected: num_cols, got: dmat.num_cols() });
+        }
+        DMatrix::concat_rows(dmats, num_cols)
+    }
+
+    fn concat_rows(dmats: &[&DMatrix], num_cols: usize) -> XGBResult<Self> {
+        let mut indptr = vec![0];
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for dmat in dmats {
+            if dmat.num_cols() > num_cols {
+                return Err(XGBError::DimensionMismatch { expected: num_cols, got: dmat.num_cols() });
+            }
+            let (dmat_indptr, dmat_indices, dmat_data) = dmat.to_csr()?;
+            let offset = data.len();
+            indptr.extend(dmat_indptr.iter().skip(1).map(|i| i + offset));
+            indices.extend(dmat_indices);
+            data.extend(dmat_data);
+        }
+
+        let mut stacked = DMatrix::from_csr(&indptr, &indices, &data, Some(num_cols))?;
+
+        // only keep info fields which every matrix has
+        let concat_info = |get: &dyn Fn(&DMatrix) -> XGBResult<Vec<f32>>| -> XGBResult<Option<Vec<f32>>> {
+            let mut values = Vec::new();
+            for dmat in dmats {
+                let dmat_values = get(dmat)?;
+                if dmat_values.is_empty() {
+                    return Ok(None);
+                }
+                values.extend(dmat_values);
+            }
+            Ok(Some(values))
+        };
+        if let Some(labels) = concat_info(&|dmat| dmat.get_labels().map(|v| v.to_vec()))? {
+            stacked.set_labels_unchecked(&labels)?;
+        }
+        if let Some(weights) = concat_info(&|dmat| dmat.get_weights().map(|v| v.to_vec()))? {
+            stacked.set_float_info(KEY_WEIGHT, &weights)?;
+        }
+        if let Some(base_margin) = concat_info(&|dmat| dmat.get_base_margin().map(|v| v.to_vec()))? {
+            stacked.set_base_margin(&base_margin)?;
+        }
+
+        let mut group_sizes = Vec::new();
+        for dmat in dmats {
+            let group_ptr = dmat.get_group()?;
+            if group_ptr.len() < 2 {
+                group_sizes.clear();
+                break;
+            }
+            group_sizes.extend(group_ptr.windows(2).map(|w| w[1] - w[0]));
+        }
+        if !group_sizes.is_empty() {
+            stacked.set_group_sizes(&group_sizes)?;
+        }
+
+        let row_ids: Option<Vec<Vec<u64>>> = dmats.iter().map(|dmat| dmat.row_ids.clone()).collect();
+        stacked.row_ids = row_ids.map(|ids| ids.concat());
+
+        Ok(stacked)
+    }
+
     /// Serialise this `DMatrix` as a binary file to given path.
     pub fn save<P: AsRef<Path>>(&self, path: P) -> XGBResult<()> {
+        self.save_with_silent(path, true)
+    }
+
+    /// Serialise this `DMatrix` as a binary file to given path, logging XGBoost's diagnostics unless `silent`
+    /// is set.
+    pub fn save_with_silent<P: AsRef<Path>>(&self, path: P, silent: bool) -> XGBResult<()> {
         debug!("Writing DMatrix to: {}", path.as_ref().display());
-        let fname = ffi::CString::new(path.as_ref().as_os_str().as_bytes()).unwrap();
-        let silent = true;
+        let fname = ffi::CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|_| XGBError::InvalidPath(path.as_ref().to_path_buf()))?;
         xgb_call!(xgboost_sys::XGDMatrixSaveBinary(self.handle, fname.as_ptr(), silent as i32))
     }
 
@@ -212,21 +620,248 @@ impl DMatrix {
         self.num_cols
     }
 
+    /// Get the raw XGBoost C API handle of this matrix, e.g. to pass it to other code linking XGBoost in the
+    /// same process.
+    ///
+    /// The handle remains owned by this `DMatrix`, and is freed when it's dropped, so must not be freed or used
+    /// after that by the receiving code.
+    pub fn as_raw_handle(&self) -> xgboost_sys::DMatrixHandle {
+        self.handle
+    }
+
+    /// Construct a matrix taking ownership of a raw XGBoost C API handle, e.g. one created by other code
+    /// linking XGBoost in the same process.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must be a valid `DMatrixHandle` created by the same XGBoost library this crate links. The
+    /// returned `DMatrix` frees it with `XGDMatrixFree` when dropped, so no other code may free it, and it must
+    /// not be wrapped in more than one `DMatrix` (use `std::mem::forget` to hand a matrix's handle over
+    /// without freeing it).
+    pub unsafe fn from_raw_handle(handle: xgboost_sys::DMatrixHandle) -> XGBResult<Self> {
+        DMatrix::new(handle)
+    }
+
+    /// Get the number of non-missing values stored in this matrix.
+    pub fn num_nonmissing(&self) -> XGBResult<usize> {
+        let mut out = 0;
+        xgb_call!(xgboost_sys::XGDMatrixNumNonMissing(self.handle, &mut out))?;
+        Ok(out as usize)
+    }
+
+    /// Check whether this matrix has the same shape, non-missing entries and labels as another, with values
+    /// compared within an absolute tolerance of `tol`.
+    pub fn approx_eq(&self, other: &DMatrix, tol: f32) -> XGBResult<bool> {
+        if self.shape() != other.shape() {
+            return Ok(false);
+        }
+
+        let (indptr, indices, data) = self.to_csr()?;
+        let (other_indptr, other_indices, other_data) = other.to_csr()?;
+        if indptr != other_indptr || indices != other_indices {
+            return Ok(false);
+        }
+        let within_tol = |a: &[f32], b: &[f32]| {
+            a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() <= tol)
+        };
+        if !within_tol(&data, &other_data) {
+            return Ok(false);
+        }
+
+        Ok(within_tol(self.get_labels()?, other.get_labels()?))
+    }
+
     /// Get the shape (rows x columns) of this matrix.
     pub fn shape(&self) -> (usize, usize) {
         (self.num_rows(), self.num_cols())
     }
 
     /// Get a new DMatrix as a containing only given indices.
+    ///
+    /// Row ids, if set, are selected in the same order as the rows. In that case every index must be less than
+    /// the number of rows, otherwise an `XGBError::InvalidParameter` error is returned.
     pub fn slice(&self, indices: &[usize]) -> XGBResult<DMatrix> {
         debug!("Slicing {} rows from DMatrix", indices.len());
+        let row_ids = match self.row_ids {
+            Some(ref ids) => {
+                let sliced = indices.iter()
+                    .map(|i| ids.get(*i).cloned())
+                    .collect::<Option<Vec<u64>>>()
+                    .ok_or_else(|| XGBError::InvalidParameter(
+                        format!("Unable to slice rows of a {} row DMatrix with row ids", self.num_rows)))?;
+                Some(sliced)
+            },
+            None => None,
+        };
+
         let mut out_handle = ptr::null_mut();
         let indices: Vec<i32> = indices.iter().map(|x| *x as i32).collect();
         xgb_call!(xgboost_sys::XGDMatrixSliceDMatrix(self.handle,
                                                      indices.as_ptr(),
                                                      indices.len() as xgboost_sys::bst_ulong,
                                                      &mut out_handle))?;
-        Ok(DMatrix::new(out_handle)?)
+        let mut sliced = DMatrix::new(out_handle)?;
+        sliced.row_ids = row_ids;
+        Ok(sliced)
+    }
+
+    /// Get a new DMatrix containing only the first `n` rows of this matrix (or every row, if it has fewer).
+    pub fn head(&self, n: usize) -> XGBResult<DMatrix> {
+        let indices: Vec<usize> = (0..n.min(self.num_rows())).collect();
+        self.slice(&indices)
+    }
+
+    /// Get a new DMatrix containing a random sample of roughly `fraction * num_rows` rows from this matrix.
+    ///
+    /// Rows are sampled without replacement, and keep their original order. Results are deterministic for a
+    /// given `seed`.
+    ///
+    /// `fraction` must be in the range (0, 1].
+    pub fn sample_rows(&self, fraction: f64, seed: u64) -> XGBResult<DMatrix> {
+        self.slice(&self.sample_row_indices(fraction, seed)?)
+    }
+
+    /// Get the sorted row indices sampled by [`sample_rows`](struct.DMatrix.html#method.sample_rows).
+    pub(crate) fn sample_row_indices(&self, fraction: f64, seed: u64) -> XGBResult<Vec<usize>> {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(XGBError::InvalidParameter(format!("sample fraction {} must be in range (0, 1]", fraction)));
+        }
+
+        let num_samples = (fraction * self.num_rows as f64).round() as usize;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut indices = rand::seq::index::sample(&mut rng, self.num_rows, num_samples).into_vec();
+        indices.sort_unstable();
+        Ok(indices)
+    }
+
+    /// Get a new DMatrix with minority classes oversampled, for training on imbalanced data.
+    ///
+    /// Rows are grouped by label, and every class with fewer than `target_ratio` times as many rows as the
+    /// largest class has rows drawn at random (with replacement) from it and added until it reaches that
+    /// ratio. All of the original rows are kept, with duplicates placed next to the row they copy. Results
+    /// are deterministic for a given `seed`.
+    ///
+    /// `target_ratio` must be in the range (0, 1], and labels must be set and finite (otherwise an
+    /// `XGBError::InvalidData` error is returned). Query groups aren't kept, so this isn't suitable for ranking.
+    pub fn resample_by_label(&self, target_ratio: f32, seed: u64) -> XGBResult<DMatrix> {
+        if !(target_ratio > 0.0 && target_ratio <= 1.0) {
+            return Err(XGBError::InvalidParameter(format!("target ratio {} must be in range (0, 1]", target_ratio)));
+        }
+        let labels = self.get_labels()?;
+        if labels.len() != self.num_rows {
+            return Err(XGBError::DimensionMismatch { expected: self.num_rows, got: labels.len() });
+        }
+        if let Some(label) = labels.iter().find(|label| !label.is_finite()) {
+            return Err(XGBError::InvalidData(format!("labels must be finite, found {}", label)));
+        }
+
+        let mut classes: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (i, label) in labels.iter().enumerate() {
+            classes.entry(label.to_bits()).or_default().push(i);
+        }
+        let largest = classes.values().map(|rows| rows.len()).max().unwrap_or(0);
+        let target = (target_ratio as f64 * largest as f64).round() as usize;
+
+        // iterate classes in label order, so the random draws don't depend on hash order
+        let mut keys: Vec<u32> = classes.keys().cloned().collect();
+        keys.sort_unstable_by(|a, b| f32::from_bits(*a).total_cmp(&f32::from_bits(*b)));
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut indices: Vec<usize> = (0..self.num_rows).collect();
+        for key in keys {
+            let rows = &classes[&key];
+            for _ in rows.len()..target {
+                indices.push(rows[rng.gen_range(0..rows.len())]);
+            }
+        }
+        indices.sort();
+        self.slice(&indices)
+    }
+
+    /// Get the quantile cut points used to discretise each feature of this matrix when training with the
+    /// `hist` tree method.
+    ///
+    /// Returns a tuple of `(indptr, values)`, where the cut points for feature `i` are stored in
+    /// `values[indptr[i]..indptr[i+1]]`.
+    ///
+    /// XGBoost only generates cut points during training, so this trains a throwaway single round `hist`
+    /// model over this matrix with given `max_bin` first. Labels therefore need to be set.
+    pub fn quantile_cuts(&self, max_bin: u32) -> XGBResult<(Vec<usize>, Vec<f32>)> {
+        let tree_params = TreeBoosterParametersBuilder::default()
+            .tree_method(TreeMethod::Hist)
+            .max_bin(max_bin)
+            .build()
+            .map_err(|err| XGBError::new(err.to_string()))?;
+        let params = BoosterParametersBuilder::default()
+            .booster_type(BoosterType::Tree(tree_params))
+            .build()
+            .map_err(|err| XGBError::new(err.to_string()))?;
+        let mut booster = Booster::new_with_cached_dmats(&params, &[self])?;
+        booster.update(self, 0)?;
+
+        let config = ffi::CString::new("{}").unwrap();
+        let mut out_indptr = ptr::null();
+        let mut out_data = ptr::null();
+        xgb_call!(xgboost_sys::XGDMatrixGetQuantileCut(self.handle,
+                                                       config.as_ptr(),
+                                                       &mut out_indptr,
+                                                       &mut out_data))?;
+        let indptr = unsafe { ffi::CStr::from_ptr(out_indptr).to_str().unwrap().to_owned() };
+        let data = unsafe { ffi::CStr::from_ptr(out_data).to_str().unwrap().to_owned() };
+
+        let indptr: Vec<u64> = array_interface::read_array(&indptr, "<u8")?;
+        let values: Vec<f32> = array_interface::read_array(&data, "<f4")?;
+        Ok((indptr.into_iter().map(|x| x as usize).collect(), values))
+    }
+
+    /// Discretise every value in this matrix into its `hist` bin index, using the cut points from
+    /// [`quantile_cuts`](struct.DMatrix.html#method.quantile_cuts).
+    ///
+    /// Returns a `num_rows x num_cols` array, where each value is replaced by the index of its bin within its
+    /// feature (so is less than `max_bin`). Missing values are set to `u32::MAX`.
+    #[cfg(feature = "ndarray")]
+    pub fn bin_features(&self, max_bin: u32) -> XGBResult<Array2<u32>> {
+        let (cut_ptr, cut_values) = self.quantile_cuts(max_bin)?;
+        let (indptr, indices, data) = self.to_csr()?;
+
+        let mut bins = Array2::from_elem((self.num_rows(), self.num_cols()), u32::MAX);
+        for row in 0..self.num_rows() {
+            for i in indptr[row]..indptr[row + 1] {
+                let feature = indices[i];
+                let cuts = &cut_values[cut_ptr[feature]..cut_ptr[feature + 1]];
+                if cuts.is_empty() {
+                    continue;
+                }
+                // cut points are the upper bounds of each bin, as in XGBoost's own bin search
+                let bin = cuts.iter().position(|cut| data[i] < *cut).unwrap_or(cuts.len() - 1);
+                bins[[row, feature]] = bin as u32;
+            }
+        }
+        Ok(bins)
+    }
+
+    /// Get summary statistics of the values in each column of this matrix, in column order.
+    ///
+    /// Missing values are skipped rather than treated as zero, so e.g. the mean is over present values
+    /// only. Columns without any present values have NaN `min`, `max` and `mean`.
+    pub fn column_stats(&self) -> XGBResult<Vec<ColumnStat>> {
+        let (_indptr, indices, data) = self.to_csr()?;
+        let mut sums = vec![0.0f64; self.num_cols];
+        let mut stats = vec![ColumnStat { min: f32::NAN, max: f32::NAN, mean: f32::NAN, nonmissing_count: 0 };
+                             self.num_cols];
+        for (feature, value) in indices.iter().zip(&data) {
+            let stat = &mut stats[*feature];
+            // f32::min/max ignore NaN, so the initial NaNs are replaced by the first value
+            stat.min = stat.min.min(*value);
+            stat.max = stat.max.max(*value);
+            stat.nonmissing_count += 1;
+            sums[*feature] += f64::from(*value);
+        }
+        for (stat, sum) in stats.iter_mut().zip(sums) {
+            if stat.nonmissing_count > 0 {
+                stat.mean = (sum / stat.nonmissing_count as f64) as f32;
+            }
+        }
+        Ok(stats)
     }
 
     /// Get ground truth labels for each row of this matrix.
@@ -235,25 +870,134 @@ impl DMatrix {
     }
 
     /// Set ground truth labels for each row of this matrix.
+    ///
+    /// Returns an `XGBError::DimensionMismatch` error unless there is exactly one label per row, or an
+    /// `XGBError::InvalidData` error if any label is NaN or infinite, since XGBoost would otherwise silently
+    /// train a meaningless model.
     pub fn set_labels(&mut self, array: &[f32]) -> XGBResult<()> {
+        if let Some(label) = array.iter().find(|label| !label.is_finite()) {
+            return Err(XGBError::InvalidData(format!("labels must be finite, found {}", label)));
+        }
+        self.set_labels_unchecked(array)
+    }
+
+    /// Set ground truth labels for each row of this matrix from an iterator, without collecting them into a
+    /// new `Vec` first.
+    ///
+    /// The iterator's length is checked against the number of rows before consuming it, and labels are
+    /// otherwise checked as in [`set_labels`](struct.DMatrix.html#method.set_labels).
+    pub fn set_labels_iter<I: ExactSizeIterator<Item = f32>>(&mut self, iter: I) -> XGBResult<()> {
+        if iter.len() != self.num_rows() {
+            return Err(XGBError::DimensionMismatch { expected: self.num_rows(), got: iter.len() });
+        }
+        let mut labels = std::mem::take(&mut self.label_buffer);
+        labels.clear();
+        labels.extend(iter);
+        let result = self.set_labels(&labels);
+        self.label_buffer = labels;
+        result
+    }
+
+    /// Set ground truth labels for each row of this matrix, without checking that they're finite.
+    ///
+    /// Returns an `XGBError::DimensionMismatch` error unless there is exactly one label per row.
+    pub fn set_labels_unchecked(&mut self, array: &[f32]) -> XGBResult<()> {
+        if array.len() != self.num_rows() {
+            return Err(XGBError::DimensionMismatch { expected: self.num_rows(), got: array.len() });
+        }
         self.set_float_info(KEY_LABEL, array)
     }
 
+    /// Set ground truth labels for each row of this matrix from a one dimensional array.
+    ///
+    /// Unlike passing `labels.as_slice().unwrap()` to [`set_labels`](struct.DMatrix.html#method.set_labels),
+    /// this also accepts non-contiguous views (e.g. every other element of a larger array), which are copied
+    /// into a contiguous buffer first.
+    #[cfg(feature = "ndarray")]
+    pub fn set_labels_ndarray(&mut self, labels: &ArrayView1<f32>) -> XGBResult<()> {
+        match labels.as_slice() {
+            Some(labels) => self.set_labels(labels),
+            None         => self.set_labels(&labels.to_vec()),
+        }
+    }
+
+    /// Set ground truth labels for each row of this matrix from string class labels, encoded as class indices
+    /// with `encoder` (adding any classes it hasn't seen before).
+    ///
+    /// Use the same encoder for every matrix of a dataset, so classes get the same indices in each, and to
+    /// decode predicted classes afterwards.
+    pub fn set_labels_encoded(&mut self, labels: &[&str], encoder: &mut LabelEncoder) -> XGBResult<()> {
+        if labels.len() != self.num_rows {
+            return Err(XGBError::DimensionMismatch { expected: self.num_rows, got: labels.len() });
+        }
+        self.set_labels(&encoder.fit_transform(labels))
+    }
+
     /// Get weights of each instance.
     pub fn get_weights(&self) -> XGBResult<&[f32]> {
         self.get_float_info(KEY_WEIGHT)
     }
 
     /// Set weights of each instance.
+    ///
+    /// Returns an `XGBError::InvalidData` error if any weight is negative, NaN or infinite.
     pub fn set_weights(&mut self, array: &[f32]) -> XGBResult<()> {
+        if let Some(weight) = array.iter().find(|weight| !(weight.is_finite() && **weight >= 0.0)) {
+            return Err(XGBError::InvalidData(format!("weights must be finite and non-negative, found {}", weight)));
+        }
         self.set_float_info(KEY_WEIGHT, array)
     }
 
+    /// Get weights of each feature (column).
+    pub fn get_feature_weights(&self) -> XGBResult<&[f32]> {
+        self.get_float_info(KEY_FEATURE_WEIGHTS)
+    }
+
+    /// Set weights of each feature (column), used as relative probabilities of selecting each feature when
+    /// column sampling (i.e. `colsample_bytree`, `colsample_bylevel` or `colsample_bynode` below 1).
+    ///
+    /// Features with a weight of 0 are only selected once every feature with a positive weight has been.
+    ///
+    /// Returns an `XGBError::DimensionMismatch` error unless there is exactly one weight per column, or an
+    /// `XGBError::InvalidData` error if any weight is negative, NaN or infinite.
+    pub fn set_feature_weights(&mut self, array: &[f32]) -> XGBResult<()> {
+        if array.len() != self.num_cols() {
+            return Err(XGBError::DimensionMismatch { expected: self.num_cols(), got: array.len() });
+        }
+        if let Some(weight) = array.iter().find(|weight| !(weight.is_finite() && **weight >= 0.0)) {
+            return Err(XGBError::InvalidData(format!("feature weights must be finite and non-negative, found {}",
+                                                     weight)));
+        }
+        self.set_float_info(KEY_FEATURE_WEIGHTS, array)
+    }
+
     /// Get base margin.
     pub fn get_base_margin(&self) -> XGBResult<&[f32]> {
         self.get_float_info(KEY_BASE_MARGIN)
     }
 
+    /// Get the ids set for each row of this matrix with [`set_row_ids`](struct.DMatrix.html#method.set_row_ids),
+    /// if any.
+    pub fn get_row_ids(&self) -> Option<&[u64]> {
+        self.row_ids.as_deref()
+    }
+
+    /// Set an arbitrary id for each row of this matrix, e.g. to track which rows of the source data it came
+    /// from.
+    ///
+    /// Ids aren't passed to XGBoost, but are stored alongside the matrix and carried through
+    /// [`slice`](struct.DMatrix.html#method.slice), [`head`](struct.DMatrix.html#method.head) and
+    /// [`vstack`](struct.DMatrix.html#method.vstack). They aren't saved with the matrix.
+    ///
+    /// Returns an `XGBError::DimensionMismatch` error unless there is exactly one id per row.
+    pub fn set_row_ids(&mut self, ids: &[u64]) -> XGBResult<()> {
+        if ids.len() != self.num_rows() {
+            return Err(XGBError::DimensionMismatch { expected: self.num_rows(), got: ids.len() });
+        }
+        self.row_ids = Some(ids.to_vec());
+        Ok(())
+    }
+
     /// Set base margin.
     ///
     /// If specified, xgboost will start from this margin, can be used to specify initial prediction to boost from.
@@ -261,9 +1005,21 @@ impl DMatrix {
         self.set_float_info(KEY_BASE_MARGIN, array)
     }
 
-    /// Set the index for the beginning and end of a group.
+    /// Set base margin to the margin predicted by given booster for this matrix, so that models trained on
+    /// this matrix continue boosting from that booster's predictions.
     ///
-    /// Needed when the learning task is ranking.
+    /// Any base margin already set on this matrix is included in the prediction, so models can be chained.
+    pub fn set_base_margin_from(&mut self, booster: &Booster) -> XGBResult<()> {
+        let margin = booster.predict_margin(self)?;
+        self.set_base_margin(&margin)
+    }
+
+    /// Set the raw query group information for this matrix.
+    ///
+    /// Needed when the learning task is ranking. Values are passed unchanged to XGBoost's `group` field, which
+    /// XGBoost converts into the cumulative offsets returned by [`get_group`](struct.DMatrix.html#method.get_group).
+    /// Prefer [`set_group_sizes`](struct.DMatrix.html#method.set_group_sizes), which also checks the groups
+    /// cover every row.
     ///
     /// See the XGBoost documentation for more information.
     pub fn set_group(&mut self, group: &[u32]) -> XGBResult<()> {
@@ -271,6 +1027,45 @@ impl DMatrix {
         self.set_uint_info(KEY_GROUP, group)
     }
 
+    /// Set query groups for ranking from the number of rows in each group.
+    ///
+    /// Groups are made up of consecutive rows, so e.g. sizes of `[2, 1, 3]` put rows 0-1, 2 and 3-5 into
+    /// separate groups. Returns an `XGBError::DimensionMismatch` error if the sizes don't add up to
+    /// [`num_rows`](struct.DMatrix.html#method.num_rows).
+    pub fn set_group_sizes(&mut self, sizes: &[u32]) -> XGBResult<()> {
+        let total: usize = sizes.iter().map(|size| *size as usize).sum();
+        if total != self.num_rows() {
+            return Err(XGBError::DimensionMismatch { expected: self.num_rows(), got: total });
+        }
+        self.set_uint_info(KEY_GROUP, sizes)
+    }
+
+    /// Set query groups for ranking from a query key per row (e.g. a `qid` column), putting each run of rows
+    /// with equal keys into its own group.
+    ///
+    /// Keys must be sorted, so all rows of a group are consecutive. Returns an `XGBError::InvalidData` error if
+    /// they aren't, or an `XGBError::DimensionMismatch` error unless there is exactly one key per row.
+    pub fn set_group_from_keys(&mut self, keys: &[u64]) -> XGBResult<()> {
+        if keys.len() != self.num_rows() {
+            return Err(XGBError::DimensionMismatch { expected: self.num_rows(), got: keys.len() });
+        }
+        if let Some(i) = keys.windows(2).position(|pair| pair[0] > pair[1]) {
+            let msg = format!("group keys must be sorted, but key {} at row {} is followed by {}",
+                              keys[i], i, keys[i + 1]);
+            return Err(XGBError::InvalidData(msg));
+        }
+
+        let mut sizes: Vec<u32> = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            if i > 0 && keys[i - 1] == *key {
+                *sizes.last_mut().unwrap() += 1;
+            } else {
+                sizes.push(1);
+            }
+        }
+        self.set_group_sizes(&sizes)
+    }
+
     /// Get the index for the beginning and end of a group.
     ///
     /// Needed when the learning task is ranking.
@@ -281,6 +1076,151 @@ impl DMatrix {
     }
 
 
+    /// Set a two dimensional float info field (e.g. `label` or `base_margin`), with one row of values per row
+    /// of this matrix.
+    ///
+    /// Allows setting e.g. multiple labels per row for multi-output models.
+    #[cfg(feature = "ndarray")]
+    pub fn set_float_info_2d(&mut self, field: &str, data: &Array2<f32>) -> XGBResult<()> {
+        let data = data.as_standard_layout();
+        let interface = array_interface::describe_array(data.as_slice().unwrap(), data.shape(), "<f4");
+        let field = ffi::CString::new(field).unwrap();
+        let interface = ffi::CString::new(interface).unwrap();
+        xgb_call!(xgboost_sys::XGDMatrixSetInfoFromInterface(self.handle, field.as_ptr(), interface.as_ptr()))
+    }
+
+    /// Set the type of each feature (column) of this matrix.
+    ///
+    /// Mainly used to mark categorical features with
+    /// [`FeatureType::Categorical`](enum.FeatureType.html#variant.Categorical), which XGBoost then splits on
+    /// by category (with the `hist` or `approx` tree methods) rather than treating as numbers.
+    pub fn set_feature_types(&mut self, feature_types: &[FeatureType]) -> XGBResult<()> {
+        if feature_types.len() != self.num_cols() {
+            return Err(XGBError::DimensionMismatch { expected: self.num_cols(), got: feature_types.len() });
+        }
+        let feature_types: Vec<String> = feature_types.iter().map(|t| t.to_string()).collect();
+        self.set_str_feature_info("feature_type", &feature_types)
+    }
+
+    /// Set names for each feature (column) of this matrix.
+    ///
+    /// Boosters created with this matrix (e.g. as the training matrix) use these names in place of XGBoost's
+    /// default `fN` names, e.g. in [`feature_importance`](struct.Booster.html#method.feature_importance).
+    pub fn set_feature_names<S: AsRef<str>>(&mut self, feature_names: &[S]) -> XGBResult<()> {
+        if feature_names.len() != self.num_cols() {
+            return Err(XGBError::DimensionMismatch { expected: self.num_cols(), got: feature_names.len() });
+        }
+        let feature_names: Vec<String> = feature_names.iter().map(|name| name.as_ref().to_owned()).collect();
+        self.set_str_feature_info("feature_name", &feature_names)
+    }
+
+    /// Get the names of each feature of this matrix, or an empty list if they haven't been set.
+    pub fn get_feature_names(&self) -> XGBResult<Vec<String>> {
+        self.get_str_feature_info("feature_name")
+    }
+
+    /// Get the type of each feature of this matrix (e.g. `q` or `c`), or an empty list if they haven't been
+    /// set.
+    pub fn get_feature_types(&self) -> XGBResult<Vec<String>> {
+        self.get_str_feature_info("feature_type")
+    }
+
+    /// Write a feature map file describing this matrix's features, one `<index>\t<name>\t<type>` line per
+    /// column.
+    ///
+    /// Uses the names and types set on this matrix, defaulting to XGBoost's `fN` names and the `q`
+    /// (quantitative) type. The file can be read with [`FeatureMap::from_file`](struct.FeatureMap.html#method.from_file) to
+    /// label text model dumps.
+    pub fn write_fmap<P: AsRef<Path>>(&self, path: P) -> XGBResult<()> {
+        let names = self.get_feature_names()?;
+        let types = self.get_feature_types()?;
+        let mut fmap = String::new();
+        for i in 0..self.num_cols() {
+            let name = names.get(i).cloned().unwrap_or_else(|| format!("f{}", i));
+            let feature_type = types.get(i).map(|t| t.as_str()).unwrap_or("q");
+            fmap.push_str(&format!("{}\t{}\t{}\n", i, name, feature_type));
+        }
+        std::fs::write(path, fmap).map_err(|err| XGBError::new(err.to_string()))
+    }
+
+    fn get_str_feature_info(&self, field: &str) -> XGBResult<Vec<String>> {
+        let field = ffi::CString::new(field).unwrap();
+        let mut out_len = 0;
+        let mut out_features = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGDMatrixGetStrFeatureInfo(self.handle,
+                                                          field.as_ptr(),
+                                                          &mut out_len,
+                                                          &mut out_features))?;
+        if out_len == 0 {
+            return Ok(Vec::new());
+        }
+        assert!(!out_features.is_null());
+        let out_ptrs = unsafe { slice::from_raw_parts(out_features, out_len as usize) };
+        Ok(out_ptrs.iter()
+           .map(|str_ptr| unsafe { ffi::CStr::from_ptr(*str_ptr).to_str().unwrap().to_owned() })
+           .collect())
+    }
+
+    fn set_str_feature_info(&mut self, field: &str, values: &[String]) -> XGBResult<()> {
+        let field = ffi::CString::new(field).unwrap();
+        let values: Vec<ffi::CString> = values.iter().map(|v| ffi::CString::new(v.as_str()).unwrap()).collect();
+        let mut ptrs: Vec<*const libc::c_char> = values.iter().map(|v| v.as_ptr()).collect();
+        xgb_call!(xgboost_sys::XGDMatrixSetStrFeatureInfo(self.handle,
+                                                          field.as_ptr(),
+                                                          ptrs.as_mut_ptr(),
+                                                          ptrs.len() as xgboost_sys::bst_ulong))
+    }
+
+    /// Extract the non-missing contents of this matrix in CSR form, as `(indptr, indices, data)` in the same
+    /// representation accepted by [`from_csr`](struct.DMatrix.html#method.from_csr).
+    pub(crate) fn to_csr(&self) -> XGBResult<(Vec<usize>, Vec<usize>, Vec<f32>)> {
+        let num_nonmissing = self.num_nonmissing()?;
+        let mut indptr: Vec<xgboost_sys::bst_ulong> = vec![0; self.num_rows + 1];
+        let mut indices: Vec<u32> = vec![0; num_nonmissing];
+        let mut data: Vec<f32> = vec![0.0; num_nonmissing];
+        let config = ffi::CString::new("{}").unwrap();
+        xgb_call!(xgboost_sys::XGDMatrixGetDataAsCSR(self.handle,
+                                                     config.as_ptr(),
+                                                     indptr.as_mut_ptr(),
+                                                     indices.as_mut_ptr(),
+                                                     data.as_mut_ptr()))?;
+        Ok((indptr.into_iter().map(|x| x as usize).collect(),
+            indices.into_iter().map(|x| x as usize).collect(),
+            data))
+    }
+
+    /// Get a copy of this matrix (including labels, weights etc.) widened to `num_cols` columns, with every
+    /// value in the added trailing columns missing.
+    pub(crate) fn pad_columns(&self, num_cols: usize) -> XGBResult<DMatrix> {
+        let (indptr, indices, data) = self.to_csr()?;
+        let mut padded = DMatrix::from_csr(&indptr, &indices, &data, Some(num_cols.max(self.num_cols)))?;
+        padded.copy_info_from(self)?;
+        Ok(padded)
+    }
+
+    /// Copy labels, weights, base margins and groups from another matrix with the same rows.
+    fn copy_info_from(&mut self, other: &DMatrix) -> XGBResult<()> {
+        let labels = other.get_labels()?;
+        if !labels.is_empty() {
+            self.set_labels_unchecked(labels)?;
+        }
+        let weights = other.get_weights()?;
+        if !weights.is_empty() {
+            self.set_float_info(KEY_WEIGHT, weights)?;
+        }
+        let base_margin = other.get_base_margin()?;
+        if !base_margin.is_empty() {
+            self.set_base_margin(base_margin)?;
+        }
+        let group_ptr = other.get_group()?;
+        if group_ptr.len() > 1 {
+            let group_sizes: Vec<u32> = group_ptr.windows(2).map(|w| w[1] - w[0]).collect();
+            self.set_group(&group_sizes)?;
+        }
+        self.row_ids = other.row_ids.clone();
+        Ok(())
+    }
+
     fn get_float_info(&self, field: &str) -> XGBResult<&[f32]> {
         let field = ffi::CString::new(field).unwrap();
         let mut out_len = 0;
@@ -290,6 +1230,10 @@ impl DMatrix {
                                                      &mut out_len,
                                                      &mut out_dptr))?;
 
+        // XGBoost may return a null pointer for unset fields, which from_raw_parts doesn't allow even if empty
+        if out_len == 0 || out_dptr.is_null() {
+            return Ok(&[]);
+        }
         Ok(unsafe { slice::from_raw_parts(out_dptr as *mut c_float, out_len as usize) })
     }
 
@@ -309,6 +1253,9 @@ impl DMatrix {
                                                     field.as_ptr(),
                                                     &mut out_len,
                                                     &mut out_dptr))?;
+        if out_len == 0 || out_dptr.is_null() {
+            return Ok(&[]);
+        }
         Ok(unsafe { slice::from_raw_parts(out_dptr as *mut c_uint, out_len as usize) })
     }
 
@@ -321,6 +1268,165 @@ impl DMatrix {
     }
 }
 
+/// Summary statistics of the present (non-missing) values in one column of a `DMatrix`, from
+/// [`DMatrix::column_stats`](struct.DMatrix.html#method.column_stats).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnStat {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub nonmissing_count: usize,
+}
+
+/// Batch of rows in sparse [CSR](https://en.wikipedia.org/wiki/Sparse_matrix#Compressed_sparse_row_(CSR,_CRS_or_Yale_format))
+/// form, produced by the callback passed to [`DMatrix::from_batches`](struct.DMatrix.html#method.from_batches).
+///
+/// As with [`from_csr`](struct.DMatrix.html#method.from_csr), column indices for row _i_ of the batch are
+/// stored in `indices[indptr[i]..indptr[i+1]]`, with their values in `data[indptr[i]..indptr[i+1]]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Batch {
+    pub indptr: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub data: Vec<f32>,
+
+    /// Ground truth label of each row, if known.
+    pub labels: Option<Vec<f32>>,
+
+    /// Weight of each row, if any.
+    pub weights: Option<Vec<f32>>,
+}
+
+impl Batch {
+    fn num_rows(&self) -> usize {
+        self.indptr.len().saturating_sub(1)
+    }
+
+    /// Check this batch is consistently sized, before handing it to XGBoost.
+    fn validate(&self) -> XGBResult<()> {
+        if self.indptr.is_empty() {
+            return Err(XGBError::InvalidData("batch indptr must have at least one entry".to_owned()));
+        }
+        if self.indices.len() != self.data.len() {
+            return Err(XGBError::DimensionMismatch { expected: self.indices.len(), got: self.data.len() });
+        }
+        if *self.indptr.last().unwrap() != self.data.len() {
+            return Err(XGBError::DimensionMismatch { expected: *self.indptr.last().unwrap(), got: self.data.len() });
+        }
+        for info in &[&self.labels, &self.weights] {
+            if let Some(ref values) = **info {
+                if values.len() != self.num_rows() {
+                    return Err(XGBError::DimensionMismatch { expected: self.num_rows(), got: values.len() });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// State shared with the C callback driving [`DMatrix::from_batches`](struct.DMatrix.html#method.from_batches).
+struct BatchIter<'a> {
+    next_batch: &'a mut dyn FnMut() -> Option<Batch>,
+    error: Option<XGBError>,
+    panic: Option<Box<dyn std::any::Any + Send>>,
+}
+
+/// Pass the next batch (if any) from a `BatchIter` to XGBoost, returning 0 once there are no more batches.
+unsafe extern "C" fn next_batch_callback(data_handle: xgboost_sys::DataIterHandle,
+                                         set_function: xgboost_sys::XGBCallbackSetData,
+                                         set_function_handle: xgboost_sys::DataHolderHandle) -> c_int {
+    let iter = &mut *(data_handle as *mut BatchIter);
+    if iter.error.is_some() || iter.panic.is_some() {
+        return 0;
+    }
+    // panics mustn't unwind into XGBoost, so hold onto them until it returns
+    let batch = match panic::catch_unwind(AssertUnwindSafe(|| (iter.next_batch)())) {
+        Ok(Some(batch)) => batch,
+        Ok(None)        => return 0,
+        Err(panic)      => {
+            iter.panic = Some(panic);
+            return 0;
+        },
+    };
+    if let Err(err) = batch.validate() {
+        iter.error = Some(err);
+        return 0;
+    }
+
+    let mut offset: Vec<i64> = batch.indptr.iter().map(|x| *x as i64).collect();
+    let mut index: Vec<c_int> = batch.indices.iter().map(|x| *x as c_int).collect();
+    let mut value = batch.data.clone();
+    let mut labels = batch.labels.clone();
+    let mut weights = batch.weights.clone();
+    let columns = batch.indices.iter().max().map(|max| max + 1).unwrap_or(0);
+    let csr = xgboost_sys::XGBoostBatchCSR {
+        size: batch.num_rows(),
+        columns,
+        offset: offset.as_mut_ptr(),
+        label: labels.as_mut().map(|l| l.as_mut_ptr()).unwrap_or(ptr::null_mut()),
+        weight: weights.as_mut().map(|w| w.as_mut_ptr()).unwrap_or(ptr::null_mut()),
+        index: index.as_mut_ptr(),
+        value: value.as_mut_ptr(),
+    };
+    // XGBoost copies the batch before returning
+    match set_function {
+        Some(set_function) => {
+            set_function(set_function_handle, csr);
+            1
+        },
+        None => 0,
+    }
+}
+
+/// Create a `DMatrix` from dense `(data, num_rows, num_cols)` in row-major order, treating NaN as missing.
+///
+/// Returns an `XGBError::DimensionMismatch` error unless `data` has `num_rows * num_cols` values, or an
+/// `XGBError::InvalidParameter` error if `num_rows` is 0.
+impl<'a> TryFrom<(&'a [f32], usize, usize)> for DMatrix {
+    type Error = XGBError;
+
+    fn try_from((data, num_rows, num_cols): (&'a [f32], usize, usize)) -> XGBResult<Self> {
+        if data.len() != num_rows * num_cols {
+            return Err(XGBError::DimensionMismatch { expected: num_rows * num_cols, got: data.len() });
+        }
+        DMatrix::from_dense_with_shape(data, num_rows, num_cols, f32::NAN)
+    }
+}
+
+/// Create a `DMatrix` from a two dimensional array with one row per instance, treating NaN as missing.
+///
+/// Arrays which aren't in standard (row-major, contiguous) layout are copied first.
+#[cfg(feature = "ndarray")]
+impl<'a> TryFrom<&'a Array2<f32>> for DMatrix {
+    type Error = XGBError;
+
+    fn try_from(array: &'a Array2<f32>) -> XGBResult<Self> {
+        let data = array.as_standard_layout();
+        DMatrix::try_from((data.as_slice().unwrap(), array.nrows(), array.ncols()))
+    }
+}
+
+/// Create a `DMatrix` from a sparse matrix in either CSR or CSC storage, keeping its shape.
+#[cfg(feature = "sprs")]
+impl<'a> TryFrom<&'a CsMat<f32>> for DMatrix {
+    type Error = XGBError;
+
+    fn try_from(mat: &'a CsMat<f32>) -> XGBResult<Self> {
+        let indptr = mat.indptr().to_proper();
+        if mat.is_csr() {
+            DMatrix::from_csr(&indptr, mat.indices(), mat.data(), Some(mat.cols()))
+        } else {
+            DMatrix::from_csc(&indptr, mat.indices(), mat.data(), Some(mat.rows()))
+        }
+    }
+}
+
+impl fmt::Display for DMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (num_rows, num_cols) = self.shape();
+        write!(f, "DMatrix[{} x {}]", num_rows, num_cols)
+    }
+}
+
 impl Drop for DMatrix {
     fn drop(&mut self) {
         xgb_call!(xgboost_sys::XGDMatrixFree(self.handle)).unwrap();
@@ -340,6 +1446,19 @@ mod tests {
         assert!(read_train_matrix().is_ok());
     }
 
+    #[test]
+    fn raw_handle() {
+        let dmat = read_train_matrix().unwrap();
+        let handle = dmat.as_raw_handle();
+        assert!(!handle.is_null());
+
+        let shape = dmat.shape();
+        std::mem::forget(dmat);
+        let dmat = unsafe { DMatrix::from_raw_handle(handle) }.unwrap();
+        assert_eq!(dmat.shape(), shape);
+        assert_eq!(dmat.get_labels().unwrap().len(), 6513);
+    }
+
     #[test]
     fn read_num_rows() {
         assert_eq!(read_train_matrix().unwrap().num_rows(), 6513);
@@ -350,6 +1469,178 @@ mod tests {
         assert_eq!(read_train_matrix().unwrap().num_cols(), 127);
     }
 
+    #[test]
+    fn shape_and_display() {
+        let dmat = read_train_matrix().unwrap();
+        assert_eq!(dmat.shape(), (dmat.num_rows(), dmat.num_cols()));
+        assert_eq!(dmat.to_string(), "DMatrix[6513 x 127]");
+    }
+
+    #[test]
+    fn column_stats() {
+        let data = [1.0, f32::NAN, 4.0,
+                    3.0, f32::NAN, -2.0,
+                    f32::NAN, f32::NAN, 1.0];
+        let dmat = DMatrix::from_dense_with_missing(&data, 3, f32::NAN).unwrap();
+        let stats = dmat.column_stats().unwrap();
+        assert_eq!(stats.len(), 3);
+
+        assert_eq!(stats[0], ColumnStat { min: 1.0, max: 3.0, mean: 2.0, nonmissing_count: 2 });
+        assert_eq!(stats[1].nonmissing_count, 0);
+        assert!(stats[1].mean.is_nan());
+        assert_eq!(stats[2], ColumnStat { min: -2.0, max: 4.0, mean: 1.0, nonmissing_count: 3 });
+    }
+
+    #[test]
+    fn get_unset_info() {
+        let dmat = DMatrix::from_dense(&[1.0, 2.0, 3.0, 4.0], 2).unwrap();
+        assert!(dmat.get_labels().unwrap().is_empty());
+        assert!(dmat.get_weights().unwrap().is_empty());
+        assert!(dmat.get_base_margin().unwrap().is_empty());
+        assert!(dmat.get_group().unwrap().len() <= 1);
+    }
+
+    #[test]
+    fn set_labels_encoded() {
+        let mut dmat = DMatrix::from_dense(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 3).unwrap();
+        let mut encoder = LabelEncoder::new();
+        dmat.set_labels_encoded(&["cat", "dog", "cat"], &mut encoder).unwrap();
+        assert_eq!(dmat.get_labels().unwrap(), &[0.0, 1.0, 0.0]);
+        assert_eq!(encoder.inverse_transform(dmat.get_labels().unwrap()).unwrap(), vec!["cat", "dog", "cat"]);
+
+        assert!(dmat.set_labels_encoded(&["cat"], &mut encoder).is_err());
+    }
+
+    #[test]
+    fn load_with_missing() {
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = tmp_dir.path().join("data.libsvm");
+        std::fs::write(&path, "1 0:0 1:2.5\n0 0:1 2:0\n").unwrap();
+
+        let dmat = DMatrix::load(&path).unwrap();
+        assert_eq!(dmat.num_nonmissing().unwrap(), 4);
+
+        let dmat_missing = DMatrix::load_with_missing(&path, 0.0).unwrap();
+        assert_eq!(dmat_missing.num_nonmissing().unwrap(), 2);
+        assert_eq!(dmat_missing.shape(), dmat.shape());
+        assert_eq!(dmat_missing.get_labels().unwrap(), &[1.0, 0.0]);
+
+        let dmat_nan = DMatrix::load_with_missing(&path, f32::NAN).unwrap();
+        assert_eq!(dmat_nan.num_nonmissing().unwrap(), 4);
+    }
+
+    #[test]
+    fn set_group_sizes() {
+        let data = [0.0; 12];
+        let mut dmat = DMatrix::from_dense(&data, 6).unwrap();
+        dmat.set_group_sizes(&[2, 1, 3]).unwrap();
+        assert_eq!(dmat.get_group().unwrap(), &[0, 2, 3, 6]);
+
+        let mut raw = DMatrix::from_dense(&data, 6).unwrap();
+        raw.set_group(&[2, 1, 3]).unwrap();
+        assert_eq!(raw.get_group().unwrap(), dmat.get_group().unwrap());
+
+        assert_eq!(dmat.set_group_sizes(&[2, 1]), Err(XGBError::DimensionMismatch { expected: 6, got: 3 }));
+    }
+
+    #[test]
+    fn set_group_from_keys() {
+        let data = [0.0; 12];
+        let mut dmat = DMatrix::from_dense(&data, 6).unwrap();
+        dmat.set_group_from_keys(&[1, 1, 2, 3, 3, 3]).unwrap();
+        assert_eq!(dmat.get_group().unwrap(), &[0, 2, 3, 6]);
+
+        assert!(dmat.set_group_from_keys(&[1, 2, 1, 3, 3, 3]).is_err());
+        assert!(dmat.set_group_from_keys(&[1, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn from_dense_labeled() {
+        let data = [1.0, 0.0, f32::NAN,
+                    0.0, 2.0, 3.0];
+        let dmat = DMatrix::from_dense_labeled(&data, 2, 3, f32::NAN, &[1.0, 0.0], Some(&[0.5, 2.0])).unwrap();
+        assert_eq!(dmat.shape(), (2, 3));
+        assert_eq!(dmat.num_nonmissing().unwrap(), 5);
+        assert_eq!(dmat.get_labels().unwrap(), &[1.0, 0.0]);
+        assert_eq!(dmat.get_weights().unwrap(), &[0.5, 2.0]);
+
+        let dmat = DMatrix::from_dense_labeled(&data, 2, 3, f32::NAN, &[1.0, 0.0], None).unwrap();
+        assert!(dmat.get_weights().unwrap().is_empty());
+
+        assert_eq!(DMatrix::from_dense_labeled(&data, 2, 3, f32::NAN, &[1.0], None).err(),
+                   Some(XGBError::DimensionMismatch { expected: 2, got: 1 }));
+        assert_eq!(DMatrix::from_dense_labeled(&data, 3, 3, f32::NAN, &[1.0, 0.0, 1.0], None).err(),
+                   Some(XGBError::DimensionMismatch { expected: 9, got: 6 }));
+    }
+
+    #[test]
+    fn get_set_feature_names() {
+        let mut dmat = DMatrix::from_dense(&[1.0, 2.0, 3.0, 4.0], 2).unwrap();
+        assert!(dmat.get_feature_names().unwrap().is_empty());
+
+        dmat.set_feature_names(&["age", "income"]).unwrap();
+        assert_eq!(dmat.get_feature_names().unwrap(), vec!["age", "income"]);
+
+        assert_eq!(dmat.set_feature_names(&["age"]), Err(XGBError::DimensionMismatch { expected: 2, got: 1 }));
+    }
+
+    #[test]
+    fn from_rows_with() {
+        let rows = vec![vec![1.0, -1.0, 0.0],
+                        vec![-2.5, 3.0, f32::NAN],
+                        vec![4.0, 5.0, -0.5]];
+        let dmat = DMatrix::from_rows_with(&rows, |value| value < 0.0).unwrap();
+        assert_eq!(dmat.shape(), (3, 3));
+        assert_eq!(dmat.num_nonmissing().unwrap(), 5);
+        let (_indptr, _indices, data) = dmat.to_csr().unwrap();
+        assert!(data.iter().all(|value| *value >= 0.0));
+
+        let ragged = vec![vec![1.0, 2.0], vec![3.0]];
+        assert!(DMatrix::from_rows_with(&ragged, |_| false).is_err());
+    }
+
+    #[test]
+    fn from_sparse_rows() {
+        let rows: Vec<HashMap<u32, f32>> = vec![
+            [(3, 1.0), (0, 2.0)].iter().cloned().collect(),
+            HashMap::new(),
+            [(1, 0.5), (2, 0.0), (4, 3.0)].iter().cloned().collect(),
+        ];
+
+        let dmat = DMatrix::from_sparse_rows(&rows, None, false).unwrap();
+        assert_eq!(dmat.shape(), (3, 5));
+        assert_eq!(dmat.num_nonmissing().unwrap(), 5);
+        let (indptr, indices, data) = dmat.to_csr().unwrap();
+        assert_eq!(indptr, vec![0, 2, 2, 5]);
+        assert_eq!(indices, vec![0, 3, 1, 2, 4]);
+        assert_eq!(data, vec![2.0, 1.0, 0.5, 0.0, 3.0]);
+
+        let dmat = DMatrix::from_sparse_rows(&rows, Some(10), true).unwrap();
+        assert_eq!(dmat.shape(), (3, 10));
+        assert_eq!(dmat.num_nonmissing().unwrap(), 4);
+    }
+
+    #[test]
+    fn write_fmap() {
+        use FeatureMap;
+
+        let mut dmat = DMatrix::from_dense(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2).unwrap();
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = tmp_dir.path().join("fmap.txt");
+
+        dmat.write_fmap(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "0\tf0\tq\n1\tf1\tq\n2\tf2\tq\n");
+
+        dmat.set_feature_names(&["age", "colour", "income"]).unwrap();
+        dmat.set_feature_types(&[FeatureType::Integer, FeatureType::Categorical, FeatureType::Quantitative])
+            .unwrap();
+        dmat.write_fmap(&path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines, vec!["0\tage\tint", "1\tcolour\tc", "2\tincome\tq"]);
+        assert!(FeatureMap::from_file(&path).is_ok());
+    }
+
     #[test]
     fn writing_and_reading() {
         let dmat = read_train_matrix().unwrap();
@@ -362,7 +1653,132 @@ mod tests {
 
         assert_eq!(dmat.num_rows(), dmat2.num_rows());
         assert_eq!(dmat.num_cols(), dmat2.num_cols());
-        // TODO: check contents as well, if possible
+        assert!(dmat.approx_eq(&dmat2, 0.0).unwrap());
+    }
+
+    #[test]
+    fn from_csr_validated() {
+        // second row has a duplicate index 1, and is unsorted
+        let indptr = [0, 2, 5];
+        let indices = [0, 2, 1, 0, 1];
+        let data = [1.0, 2.0, 3.0, 4.0, 5.0];
+
+        match DMatrix::from_csr_validated(&indptr, &indices, &data, None, false) {
+            Err(XGBError::InvalidCsr(msg)) => assert!(msg.contains("row 1")),
+            result => panic!("unexpected result: {:?}", result.map(|dmat| dmat.shape())),
+        }
+
+        let dmat = DMatrix::from_csr_validated(&indptr, &indices, &data, None, true).unwrap();
+        assert_eq!(dmat.shape(), (2, 3));
+        let (csr_indptr, csr_indices, csr_data) = dmat.to_csr().unwrap();
+        assert_eq!(csr_indptr, vec![0, 2, 4]);
+        assert_eq!(csr_indices, vec![0, 2, 0, 1]);
+        assert_eq!(csr_data, vec![1.0, 2.0, 4.0, 8.0]);
+
+        assert!(DMatrix::from_csr_validated(&[0, 2, 3], &[0, 1, 1], &[1.0, 2.0, 3.0], None, false).is_ok());
+    }
+
+    #[test]
+    fn from_batches() {
+        let mut batches = vec![
+            Batch {
+                indptr: vec![0, 2, 3],
+                indices: vec![0, 2, 1],
+                data: vec![1.0, 2.0, 3.0],
+                labels: Some(vec![1.0, 0.0]),
+                weights: None,
+            },
+            Batch {
+                indptr: vec![0, 1, 1, 3],
+                indices: vec![1, 0, 3],
+                data: vec![4.0, 5.0, 6.0],
+                labels: Some(vec![0.0, 1.0, 1.0]),
+                weights: None,
+            },
+        ].into_iter();
+        let dmat = DMatrix::from_batches(|| batches.next()).unwrap();
+        assert_eq!(dmat.shape(), (5, 4));
+        assert_eq!(dmat.get_labels().unwrap(), &[1.0, 0.0, 0.0, 1.0, 1.0]);
+
+        let mut bad = Some(Batch { indptr: vec![0, 2], indices: vec![0], data: vec![1.0], ..Default::default() });
+        assert!(DMatrix::from_batches(|| bad.take()).is_err());
+    }
+
+    #[test]
+    fn from_libsvm_reader() {
+        use std::fs::File;
+        use std::io::{BufReader, Cursor};
+
+        let path = "xgboost-sys/xgboost/demo/data/agaricus.txt.train";
+        let dmat = DMatrix::from_libsvm_reader(BufReader::new(File::open(path).unwrap()), None).unwrap();
+        let loaded = DMatrix::load(path).unwrap();
+        assert_eq!(dmat.shape(), loaded.shape());
+        assert_eq!(dmat.get_labels().unwrap(), loaded.get_labels().unwrap());
+
+        // final line has no trailing newline
+        let text = "1 qid:1 0:1.5 2:3\n# comment\n0 qid:1 1:2\n\n1 qid:2 0:4";
+        let dmat = DMatrix::from_libsvm_reader(Cursor::new(text), None).unwrap();
+        assert_eq!(dmat.shape(), (3, 3));
+        assert_eq!(dmat.get_labels().unwrap(), &[1.0, 0.0, 1.0]);
+        assert_eq!(dmat.get_group().unwrap(), &[0, 2, 3]);
+
+        let dmat = DMatrix::from_libsvm_reader(Cursor::new("1 0:1.5"), Some(4)).unwrap();
+        assert_eq!(dmat.shape(), (1, 4));
+
+        match DMatrix::from_libsvm_reader(Cursor::new("1 0:1.5\n0 x:2"), None) {
+            Err(XGBError::InvalidData(msg)) => assert!(msg.contains("line 2")),
+            result => panic!("unexpected result: {:?}", result.map(|dmat| dmat.shape())),
+        }
+    }
+
+    #[test]
+    fn load_many() {
+        let path = "xgboost-sys/xgboost/demo/data/agaricus.txt.train";
+        let dmat = DMatrix::load(path).unwrap();
+
+        let stacked = DMatrix::load_many(&[path, path], None).unwrap();
+        assert_eq!(stacked.shape(), (dmat.num_rows() * 2, dmat.num_cols()));
+        assert_eq!(stacked.num_nonmissing().unwrap(), dmat.num_nonmissing().unwrap() * 2);
+        let labels = dmat.get_labels().unwrap();
+        assert_eq!(stacked.get_labels().unwrap(), &[labels, labels].concat()[..]);
+
+        let wide = DMatrix::load_many(&[path, path], Some(200)).unwrap();
+        assert_eq!(wide.shape(), (dmat.num_rows() * 2, 200));
+    }
+
+    #[test]
+    fn vstack_mismatched_columns() {
+        let narrow = DMatrix::from_dense(&[1.0, 2.0], 1).unwrap();
+        let wide = DMatrix::from_dense(&[1.0, 2.0, 3.0], 1).unwrap();
+        assert_eq!(DMatrix::vstack(&[&narrow, &wide]).err(),
+                   Some(XGBError::DimensionMismatch { expected: 2, got: 3 }));
+    }
+
+    #[test]
+    fn load_and_save_with_silent() {
+        let dmat = DMatrix::load_with_silent("xgboost-sys/xgboost/demo/data/agaricus.txt.train", false).unwrap();
+        assert_eq!(dmat.num_rows(), 6513);
+
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let out_path = tmp_dir.path().join("dmat.bin");
+        dmat.save_with_silent(&out_path, false).unwrap();
+        assert!(DMatrix::load_with_silent(&out_path, false).unwrap().approx_eq(&dmat, 0.0).unwrap());
+    }
+
+    #[test]
+    fn approx_eq() {
+        let dmat = read_train_matrix().unwrap();
+        assert!(dmat.approx_eq(&dmat, 0.0).unwrap());
+
+        let mut modified = read_train_matrix().unwrap();
+        let mut labels = modified.get_labels().unwrap().to_vec();
+        labels[0] += 0.5;
+        modified.set_labels(&labels).unwrap();
+        assert!(!dmat.approx_eq(&modified, 0.1).unwrap());
+        assert!(dmat.approx_eq(&modified, 1.0).unwrap());
+
+        let sliced = dmat.slice(&[0, 1, 2]).unwrap();
+        assert!(!dmat.approx_eq(&sliced, 1.0).unwrap());
     }
 
     #[test]
@@ -370,9 +1786,48 @@ mod tests {
         let mut dmat = read_train_matrix().unwrap();
         assert_eq!(dmat.get_labels().unwrap().len(), 6513);
 
-        let label = [0.1, 0.0 -4.5, 11.29842, 333333.33];
+        let label: Vec<f32> = (0..6513).map(|i| i as f32 * 0.5).collect();
         assert!(dmat.set_labels(&label).is_ok());
-        assert_eq!(dmat.get_labels().unwrap(), label);
+        assert_eq!(dmat.get_labels().unwrap(), &label[..]);
+    }
+
+    #[test]
+    fn set_labels_iter() {
+        let mut dmat = read_train_matrix().unwrap();
+        assert!(dmat.set_labels_iter((0..10).map(|i| i as f32)).is_err());
+
+        assert!(dmat.set_labels_iter((0..6513).map(|i| i as f32)).is_ok());
+        let expected: Vec<f32> = (0..6513).map(|i| i as f32).collect();
+        assert_eq!(dmat.get_labels().unwrap(), &expected[..]);
+
+        assert!(dmat.set_labels_iter((0..6513).map(|i| (i % 2) as f32)).is_ok());
+        assert_eq!(dmat.get_labels().unwrap()[..3], [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn set_labels_dimension_mismatch() {
+        let mut dmat = read_train_matrix().unwrap();
+        let result = dmat.set_labels(&[0.1, 0.0, -4.5, 11.29842, 333333.33]);
+        assert_eq!(result, Err(XGBError::DimensionMismatch { expected: 6513, got: 5 }));
+        assert_eq!(dmat.get_labels().unwrap().len(), 6513);
+    }
+
+    #[test]
+    fn set_labels_non_finite() {
+        let mut dmat = DMatrix::from_dense(&[1.0, 2.0, 3.0], 3).unwrap();
+        match dmat.set_labels(&[1.0, f32::NAN, 0.0]) {
+            Err(XGBError::InvalidData(msg)) => assert!(msg.contains("NaN")),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(dmat.set_labels(&[1.0, f32::INFINITY, 0.0]).is_err());
+        assert!(dmat.get_labels().unwrap().is_empty());
+
+        assert!(dmat.set_labels_unchecked(&[1.0, f32::NAN, 0.0]).is_ok());
+        assert!(dmat.get_labels().unwrap()[1].is_nan());
+
+        assert!(dmat.set_weights(&[1.0, -1.0, 1.0]).is_err());
+        assert!(dmat.set_weights(&[1.0, f32::NAN, 1.0]).is_err());
+        assert!(dmat.set_weights(&[1.0, 0.0, 1.0]).is_ok());
     }
 
     #[test]
@@ -385,6 +1840,17 @@ mod tests {
         assert_eq!(dmat.get_weights().unwrap(), weight);
     }
 
+    #[test]
+    fn get_set_feature_weights() {
+        let mut dmat = DMatrix::from_dense(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2).unwrap();
+        assert!(dmat.get_feature_weights().unwrap().is_empty());
+
+        assert!(dmat.set_feature_weights(&[1.0, 2.0]).is_err());
+        assert!(dmat.set_feature_weights(&[1.0, -1.0, 1.0]).is_err());
+        assert!(dmat.set_feature_weights(&[1.0, 0.0, 0.5]).is_ok());
+        assert_eq!(dmat.get_feature_weights().unwrap(), &[1.0, 0.0, 0.5]);
+    }
+
     #[test]
     fn get_set_base_margin() {
         let mut dmat = read_train_matrix().unwrap();
@@ -398,6 +1864,155 @@ mod tests {
         assert_eq!(dmat.get_base_margin().unwrap(), base_margin);
     }
 
+    #[test]
+    fn quantile_cuts() {
+        let dmat = read_train_matrix().unwrap();
+        let max_bin = 16;
+        let (indptr, values) = dmat.quantile_cuts(max_bin).unwrap();
+        assert_eq!(indptr.len(), dmat.num_cols() + 1);
+        assert_eq!(*indptr.last().unwrap(), values.len());
+        assert!(values.len() <= dmat.num_cols() * max_bin as usize);
+    }
+
+    #[test]
+    fn try_from_dense_tuple() {
+        let data = [1.0, f32::NAN, 3.0, 0.0, 5.0, 6.0];
+        let dmat = DMatrix::try_from((&data[..], 2, 3)).unwrap();
+        assert_eq!(dmat.shape(), (2, 3));
+        assert_eq!(dmat.num_nonmissing().unwrap(), 5);
+
+        assert!(DMatrix::try_from((&data[..], 4, 3)).is_err());
+        match DMatrix::try_from((&[][..], 0, 3)) {
+            Err(XGBError::InvalidParameter(_)) => (),
+            result => panic!("unexpected result: {:?}", result.map(|dmat| dmat.shape())),
+        }
+        assert!(DMatrix::from_dense_with_missing(&[], 0, f32::NAN).is_err());
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn try_from_ndarray() {
+        let array = Array2::from_shape_vec((3, 2), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+        assert_eq!(DMatrix::try_from(&array).unwrap().shape(), (3, 2));
+
+        // non-standard layout is copied before conversion
+        let transposed = array.t().to_owned();
+        let dmat = DMatrix::try_from(&transposed.reversed_axes()).unwrap();
+        assert_eq!(dmat.shape(), (3, 2));
+    }
+
+    #[cfg(feature = "sprs")]
+    #[test]
+    fn try_from_sprs() {
+        let csr = CsMat::new((3, 4), vec![0, 2, 2, 3], vec![0, 3, 1], vec![1.0, 2.0, 3.0]);
+        let dmat = DMatrix::try_from(&csr).unwrap();
+        assert_eq!(dmat.shape(), (3, 4));
+        assert_eq!(dmat.num_nonmissing().unwrap(), 3);
+
+        let csc = csr.to_csc();
+        assert_eq!(DMatrix::try_from(&csc).unwrap().shape(), (3, 4));
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn bin_features() {
+        let num_rows = 200;
+        let data: Vec<f32> = (0..num_rows * 2).map(|i| ((i * 37) % 101 + 1) as f32).collect();
+        let mut dmat = DMatrix::from_dense(&data, num_rows).unwrap();
+        dmat.set_labels(&vec![0.0; num_rows]).unwrap();
+
+        let max_bin = 8;
+        let bins = dmat.bin_features(max_bin).unwrap();
+        assert_eq!(bins.shape(), &[num_rows, 2]);
+        for column in bins.columns() {
+            let distinct: std::collections::BTreeSet<u32> = column.iter().cloned().collect();
+            assert!(distinct.len() > 1);
+            assert!(distinct.len() <= max_bin as usize);
+            assert!(distinct.iter().all(|bin| *bin < max_bin));
+        }
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn set_labels_ndarray() {
+        use ndarray::{s, Array1};
+
+        let data = [0.0; 8];
+        let mut dmat = DMatrix::from_dense(&data, 4).unwrap();
+        let all_labels = Array1::from(vec![1.0, -1.0, 2.0, -2.0, 3.0, -3.0, 4.0, -4.0]);
+        let labels = all_labels.slice(s![..;2]);
+        assert!(labels.as_slice().is_none());
+
+        dmat.set_labels_ndarray(&labels).unwrap();
+        assert_eq!(dmat.get_labels().unwrap(), &[1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn set_float_info_2d() {
+        let data = [1.0, 0.0,
+                    0.0, 1.0,
+                    1.0, 1.0,
+                    0.0, 0.0];
+        let mut dmat = DMatrix::from_dense(&data, 4).unwrap();
+        let labels = Array2::from_shape_vec((4, 2), vec![1.0, -1.0,
+                                                         2.0, -2.0,
+                                                         3.0, -3.0,
+                                                         4.0, -4.0]).unwrap();
+        dmat.set_float_info_2d(KEY_LABEL, &labels).unwrap();
+        assert_eq!(dmat.get_labels().unwrap(), labels.as_slice().unwrap());
+
+        let tree_params = TreeBoosterParametersBuilder::default()
+            .tree_method(TreeMethod::Hist)
+            .build()
+            .unwrap();
+        let params = BoosterParametersBuilder::default()
+            .booster_type(BoosterType::Tree(tree_params))
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat]).unwrap();
+        booster.update(&dmat, 0).unwrap();
+        assert_eq!(booster.predict(&dmat).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn set_base_margin_from() {
+        use parameters::{learning, TrainingParametersBuilder};
+
+        let train = |dtrain: &DMatrix| {
+            let tree_params = TreeBoosterParametersBuilder::default()
+                .eta(0.1)
+                .build().unwrap();
+            let learning_params = learning::LearningTaskParametersBuilder::default()
+                .objective(learning::Objective::BinaryLogistic)
+                .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+                .build().unwrap();
+            let booster_params = BoosterParametersBuilder::default()
+                .booster_type(BoosterType::Tree(tree_params))
+                .learning_params(learning_params)
+                .verbose(false)
+                .build().unwrap();
+            let training_params = TrainingParametersBuilder::default()
+                .booster_params(booster_params)
+                .dtrain(dtrain)
+                .boost_rounds(3)
+                .build().unwrap();
+            Booster::train(&training_params).unwrap()
+        };
+
+        let dtrain = read_train_matrix().unwrap();
+        let first = train(&dtrain);
+        let scratch_loss = first.evaluate(&dtrain).unwrap()["logloss"];
+
+        let mut dtrain_warm = read_train_matrix().unwrap();
+        dtrain_warm.set_base_margin_from(&first).unwrap();
+        assert_eq!(dtrain_warm.get_base_margin().unwrap(), &first.predict_margin(&dtrain).unwrap()[..]);
+
+        let second = train(&dtrain_warm);
+        let warm_loss = second.evaluate(&dtrain_warm).unwrap()["logloss"];
+        assert!(warm_loss < scratch_loss, "{} >= {}", warm_loss, scratch_loss);
+    }
+
     #[test]
     fn get_set_group() {
         let mut dmat = read_train_matrix().unwrap();
@@ -423,6 +2038,40 @@ mod tests {
         assert_eq!(dmat.num_cols(), 10);
     }
 
+    #[test]
+    fn from_csr_empty() {
+        match DMatrix::from_csr(&[], &[], &[], None) {
+            Err(XGBError::InvalidCsr(_)) => (),
+            result => panic!("unexpected result: {:?}", result.map(|dmat| dmat.shape())),
+        }
+        match DMatrix::from_csr(&[0], &[], &[], Some(3)) {
+            Err(XGBError::InvalidCsr(_)) => (),
+            result => panic!("unexpected result: {:?}", result.map(|dmat| dmat.shape())),
+        }
+    }
+
+    #[test]
+    fn from_csc_empty() {
+        match DMatrix::from_csc(&[], &[], &[], None) {
+            Err(XGBError::InvalidCsr(_)) => (),
+            result => panic!("unexpected result: {:?}", result.map(|dmat| dmat.shape())),
+        }
+        match DMatrix::from_csc(&[0], &[], &[], Some(3)) {
+            Err(XGBError::InvalidCsr(_)) => (),
+            result => panic!("unexpected result: {:?}", result.map(|dmat| dmat.shape())),
+        }
+    }
+
+    #[test]
+    fn from_csr_rows_without_features() {
+        let dmat = DMatrix::from_csr(&[0, 0, 0], &[], &[], None).unwrap();
+        assert_eq!(dmat.num_rows(), 2);
+        assert_eq!(dmat.num_cols(), 0);
+
+        let dmat = DMatrix::from_csr(&[0, 0, 0], &[], &[], Some(3)).unwrap();
+        assert_eq!(dmat.shape(), (2, 3));
+    }
+
     #[test]
     fn from_csc() {
         let indptr = [0, 2, 3, 6, 8];
@@ -455,6 +2104,24 @@ mod tests {
         assert_eq!(dmat.num_cols(), 1);
     }
 
+    #[test]
+    fn row_ids() {
+        let mut dmat = DMatrix::from_dense(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0], 4).unwrap();
+        assert_eq!(dmat.get_row_ids(), None);
+        assert!(dmat.set_row_ids(&[10, 11]).is_err());
+        dmat.set_row_ids(&[10, 11, 12, 13]).unwrap();
+
+        let sliced = dmat.slice(&[3, 0, 2]).unwrap();
+        assert_eq!(sliced.get_row_ids(), Some(&[13, 10, 12][..]));
+        assert_eq!(dmat.head(2).unwrap().get_row_ids(), Some(&[10, 11][..]));
+        assert!(dmat.slice(&[4]).is_err());
+
+        let stacked = DMatrix::vstack(&[&sliced, &dmat]).unwrap();
+        assert_eq!(stacked.get_row_ids(), Some(&[13, 10, 12, 10, 11, 12, 13][..]));
+        let without_ids = DMatrix::from_dense(&[1.0, 2.0], 1).unwrap();
+        assert_eq!(DMatrix::vstack(&[&dmat, &without_ids]).unwrap().get_row_ids(), None);
+    }
+
     #[test]
     fn slice_from_indices() {
         let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
@@ -470,6 +2137,59 @@ mod tests {
         assert_eq!(dmat.slice(&[10, 11, 12]).unwrap().shape(), (3, 2));
     }
 
+    #[test]
+    fn head() {
+        let dmat = read_train_matrix().unwrap();
+        let head = dmat.head(10).unwrap();
+        assert_eq!(head.shape(), (10, dmat.num_cols()));
+        assert_eq!(head.get_labels().unwrap(), &dmat.get_labels().unwrap()[..10]);
+
+        assert_eq!(dmat.head(dmat.num_rows() + 10).unwrap().num_rows(), dmat.num_rows());
+    }
+
+    #[test]
+    fn sample_rows() {
+        let dmat = read_train_matrix().unwrap();
+        let sample = dmat.sample_rows(0.5, 42).unwrap();
+        assert_eq!(sample.num_rows(), (dmat.num_rows() as f64 * 0.5).round() as usize);
+        assert_eq!(sample.num_cols(), dmat.num_cols());
+
+        let again = dmat.sample_rows(0.5, 42).unwrap();
+        assert_eq!(sample.get_labels().unwrap(), again.get_labels().unwrap());
+
+        assert_eq!(dmat.sample_rows(1.0, 0).unwrap().num_rows(), dmat.num_rows());
+        assert!(dmat.sample_rows(0.0, 0).is_err());
+        assert!(dmat.sample_rows(1.5, 0).is_err());
+    }
+
+    #[test]
+    fn resample_by_label() {
+        let mut dmat = DMatrix::from_dense(&(0..100).map(|i| i as f32).collect::<Vec<f32>>(), 100).unwrap();
+        let labels: Vec<f32> = (0..100).map(|i| if i % 10 == 0 { 1.0 } else { 0.0 }).collect();
+        dmat.set_labels(&labels).unwrap();
+        let count_positive = |dmat: &DMatrix| dmat.get_labels().unwrap().iter().filter(|l| **l == 1.0).count();
+
+        let resampled = dmat.resample_by_label(0.5, 42).unwrap();
+        assert_eq!(count_positive(&resampled), 45);
+        assert_eq!(resampled.num_rows() - count_positive(&resampled), 90);
+        assert_eq!(resampled.num_cols(), dmat.num_cols());
+        let again = dmat.resample_by_label(0.5, 42).unwrap();
+        assert_eq!(resampled.get_labels().unwrap(), again.get_labels().unwrap());
+
+        // classes already at the ratio are left alone
+        assert_eq!(dmat.resample_by_label(0.1, 0).unwrap().num_rows(), 100);
+        assert!(dmat.resample_by_label(0.0, 0).is_err());
+        assert!(dmat.resample_by_label(1.5, 0).is_err());
+
+        let mut nan_labels = labels.clone();
+        nan_labels[3] = f32::NAN;
+        dmat.set_labels_unchecked(&nan_labels).unwrap();
+        match dmat.resample_by_label(0.5, 0) {
+            Err(XGBError::InvalidData(_)) => (),
+            result => panic!("unexpected result: {:?}", result.map(|dmat| dmat.shape())),
+        }
+    }
+
     #[test]
     fn slice() {
         let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0];
@@ -4,21 +4,47 @@ use std;
 use std::ffi::CStr;
 use std::fmt::{self, Display};
 use std::error::Error;
+use std::path::PathBuf;
 
 use xgboost_sys;
 
 /// Convenience return type for most operations which can return an `XGBError`.
 pub type XGBResult<T> = std::result::Result<T, XGBError>;
 
-/// Wrap errors returned by the XGBoost library.
-#[derive(Debug, Eq, PartialEq)]
-pub struct XGBError {
-    desc: String,
+/// Errors returned by this crate, either from the XGBoost library itself or from checks made before calling it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum XGBError {
+    /// A call to the XGBoost C API failed, with the return code and XGBoost's last error message.
+    CApi { code: i32, message: String },
+
+    /// An array passed in didn't have the expected number of values (e.g. one label per row).
+    DimensionMismatch { expected: usize, got: usize },
+
+    /// A path couldn't be used, e.g. because no file exists there.
+    InvalidPath(PathBuf),
+
+    /// A parameter value was invalid or inconsistent with other parameters.
+    InvalidParameter(String),
+
+    /// Values passed in were unusable, e.g. NaN labels or negative weights.
+    InvalidData(String),
+
+    /// Sparse CSR (or CSC) data was malformed, e.g. a row had duplicate column indices.
+    InvalidCsr(String),
+
+    /// A matrix has more columns than the model it's used with has features.
+    FeatureCountMismatch { model: usize, data: usize },
+
+    /// The largest training label is outside the range of classes of a multiclass objective.
+    LabelOutOfRange { max_label: f32, num_class: u32 },
+
+    /// Any other error.
+    Other(String),
 }
 
 impl XGBError {
     pub(crate) fn new<S: Into<String>>(desc: S) -> Self {
-        XGBError { desc: desc.into() }
+        XGBError::Other(desc.into())
     }
 
     /// Check the return value from an XGBoost FFI call, and return the last error message on
@@ -30,16 +56,16 @@ impl XGBError {
     pub(crate) fn check_return_value(ret_val: i32) -> XGBResult<()> {
         match ret_val {
             0  => Ok(()),
-            -1 => Err(XGBError::from_xgboost()),
+            -1 => Err(XGBError::from_xgboost(ret_val)),
             _  => panic!("unexpected return value '{}', expected 0 or -1", ret_val),
         }
     }
 
     /// Get the last error message from XGBoost.
-    fn from_xgboost() -> Self {
+    fn from_xgboost(code: i32) -> Self {
         let c_str = unsafe { CStr::from_ptr(xgboost_sys::XGBGetLastError()) };
         let str_slice = c_str.to_str().unwrap();
-        XGBError { desc: str_slice.to_owned() }
+        XGBError::CApi { code, message: str_slice.to_owned() }
     }
 }
 
@@ -47,7 +73,22 @@ impl Error for XGBError {}
 
 impl Display for XGBError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "XGBoost error: {}", &self.desc)
+        match *self {
+            XGBError::CApi { ref message, .. } => write!(f, "XGBoost error: {}", message),
+            XGBError::DimensionMismatch { expected, got } =>
+                write!(f, "Dimension mismatch: expected {} values, got {}", expected, got),
+            XGBError::InvalidPath(ref path) => write!(f, "Invalid path: {}", path.display()),
+            XGBError::InvalidParameter(ref desc) => write!(f, "Invalid parameter: {}", desc),
+            XGBError::InvalidData(ref desc) => write!(f, "Invalid data: {}", desc),
+            XGBError::InvalidCsr(ref desc) => write!(f, "Invalid sparse data: {}", desc),
+            XGBError::FeatureCountMismatch { model, data } =>
+                write!(f, "Feature count mismatch: model was trained with {} features, but data has {} columns",
+                       model, data),
+            XGBError::LabelOutOfRange { max_label, num_class } =>
+                write!(f, "Label {} is out of range for multiclass objective with num_class={}, \
+                           labels must be in [0, {})", max_label, num_class, num_class),
+            XGBError::Other(ref desc) => write!(f, "XGBoost error: {}", desc),
+        }
     }
 }
 
@@ -61,6 +102,6 @@ mod tests {
         assert_eq!(result, Ok(()));
 
         let result = XGBError::check_return_value(-1);
-        assert_eq!(result, Err(XGBError { desc: "".to_owned() }));
+        assert_eq!(result, Err(XGBError::CApi { code: -1, message: "".to_owned() }));
     }
 }
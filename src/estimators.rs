@@ -0,0 +1,264 @@
+//! High level estimators in the style of scikit-learn, which build the `DMatrix`, pick a suitable objective,
+//! train and hold onto the resulting `Booster`.
+//!
+//! Features are passed as dense row-major slices, with NaN treated as missing.
+//!
+//! ```
+//! use xgboost::estimators::XgbClassifier;
+//!
+//! let x = &[1.0, 2.0,
+//!           1.5, 2.5,
+//!           8.0, 9.0,
+//!           8.5, 9.5];
+//! let y = &[0.0, 0.0, 1.0, 1.0];
+//!
+//! let mut clf = XgbClassifier::new();
+//! clf.fit(x, 4, y).unwrap();
+//! println!("{:?}", clf.predict(&[8.2, 9.1], 1).unwrap());
+//! ```
+use super::{Booster, DMatrix, XGBResult, XGBError};
+use parameters::{BoosterParameters, TrainingParametersBuilder};
+use parameters::learning::Objective;
+
+/// Default number of boosting rounds used by estimators.
+const DEFAULT_BOOST_ROUNDS: u32 = 100;
+
+/// Build a matrix from `num_rows` rows of dense row-major features.
+///
+/// Returns an `XGBError::InvalidParameter` error if `x` can't be split into `num_rows` equally sized rows.
+fn feature_matrix(x: &[f32], num_rows: usize) -> XGBResult<DMatrix> {
+    if num_rows == 0 {
+        return Err(XGBError::InvalidParameter("num_rows must be greater than 0".to_owned()));
+    }
+    if num_rows * (x.len() / num_rows) != x.len() {
+        let msg = format!("Unable to split {} values into {} rows", x.len(), num_rows);
+        return Err(XGBError::InvalidParameter(msg));
+    }
+    DMatrix::from_dense_with_missing(x, num_rows, f32::NAN)
+}
+
+/// Build a training matrix from dense row-major features and one label per row.
+fn training_matrix(x: &[f32], num_rows: usize, y: &[f32]) -> XGBResult<DMatrix> {
+    if y.len() != num_rows {
+        return Err(XGBError::DimensionMismatch { expected: num_rows, got: y.len() });
+    }
+    let mut dtrain = feature_matrix(x, num_rows)?;
+    dtrain.set_labels(y)?;
+    Ok(dtrain)
+}
+
+/// Build a matrix to predict with `booster` from dense row-major features, which must have one value per
+/// feature the model was trained with.
+fn prediction_matrix(booster: &Booster, x: &[f32], num_rows: usize) -> XGBResult<DMatrix> {
+    let num_features = booster.num_features()?;
+    if x.len() != num_rows * num_features {
+        return Err(XGBError::DimensionMismatch { expected: num_rows * num_features, got: x.len() });
+    }
+    feature_matrix(x, num_rows)
+}
+
+/// Train a Booster on `dtrain`, with given parameters and objective.
+fn train(params: &BoosterParameters, objective: Objective, boost_rounds: u32, dtrain: &DMatrix)
+         -> XGBResult<Booster> {
+    let mut learning_params = params.learning_params().clone();
+    learning_params.set_objective(objective);
+    let mut booster_params = params.clone();
+    booster_params.set_learning_params(learning_params);
+
+    let training_params = TrainingParametersBuilder::default()
+        .dtrain(dtrain)
+        .booster_params(booster_params)
+        .boost_rounds(boost_rounds)
+        .build()
+        .map_err(|err| XGBError::InvalidParameter(err.to_string()))?;
+    Booster::train(&training_params)
+}
+
+fn not_fitted() -> XGBError {
+    XGBError::InvalidParameter("estimator must be fitted before predicting".to_owned())
+}
+
+/// Gradient boosted classifier, for labels which are class indices `0, 1, ..., n - 1`.
+///
+/// Trains with the `binary:logistic` objective for two classes, or `multi:softprob` for more, overriding any
+/// objective set in its parameters.
+pub struct XgbClassifier {
+    params: BoosterParameters,
+    boost_rounds: u32,
+    booster: Option<Booster>,
+    num_classes: usize,
+}
+
+impl XgbClassifier {
+    /// Create a new classifier using default parameters and 100 boosting rounds.
+    pub fn new() -> Self {
+        XgbClassifier::with_params(BoosterParameters::default(), DEFAULT_BOOST_ROUNDS)
+    }
+
+    /// Create a new classifier using given parameters (apart from the objective) and number of boosting rounds.
+    pub fn with_params(params: BoosterParameters, boost_rounds: u32) -> Self {
+        XgbClassifier { params, boost_rounds, booster: None, num_classes: 0 }
+    }
+
+    /// Train this classifier on `num_rows` rows of dense features `x`, with class labels `y`, replacing any
+    /// previously trained model.
+    pub fn fit(&mut self, x: &[f32], num_rows: usize, y: &[f32]) -> XGBResult<()> {
+        if let Some(label) = y.iter().find(|label| !(**label >= 0.0 && label.fract() == 0.0)) {
+            return Err(XGBError::InvalidData(format!("class label {} is not a non-negative integer", label)));
+        }
+        let num_classes = (y.iter().cloned().fold(0.0, f32::max) as usize + 1).max(2);
+        let objective = if num_classes == 2 {
+            Objective::BinaryLogistic
+        } else {
+            Objective::MultiSoftprob(num_classes as u32)
+        };
+
+        let dtrain = training_matrix(x, num_rows, y)?;
+        self.booster = Some(train(&self.params, objective, self.boost_rounds, &dtrain)?);
+        self.num_classes = num_classes;
+        Ok(())
+    }
+
+    /// Predict the most probable class of each of `num_rows` rows of dense features `x`.
+    pub fn predict(&self, x: &[f32], num_rows: usize) -> XGBResult<Vec<u32>> {
+        let booster = self.booster.as_ref().ok_or_else(not_fitted)?;
+        let dmat = prediction_matrix(booster, x, num_rows)?;
+        booster.predict_labels(&dmat, 0.5)
+    }
+
+    /// Predict the probability of every class for each of `num_rows` rows of dense features `x`.
+    ///
+    /// Returns a tuple of (data, (num_rows, num_classes)), with data in row-major order. Binary classifiers
+    /// return both columns (i.e. `1 - p` and `p`).
+    pub fn predict_proba(&self, x: &[f32], num_rows: usize) -> XGBResult<(Vec<f32>, (usize, usize))> {
+        let booster = self.booster.as_ref().ok_or_else(not_fitted)?;
+        let dmat = prediction_matrix(booster, x, num_rows)?;
+        let preds = booster.predict(&dmat)?;
+        if self.num_classes == 2 {
+            let proba = preds.iter().flat_map(|p| vec![1.0 - p, *p]).collect();
+            return Ok((proba, (num_rows, 2)));
+        }
+        Ok((preds, (num_rows, self.num_classes)))
+    }
+
+    /// Get the number of classes seen when fitting, or 0 if not fitted yet.
+    pub fn num_classes(&self) -> usize {
+        self.num_classes
+    }
+
+    /// Get the trained model, or `None` if not fitted yet.
+    pub fn booster(&self) -> Option<&Booster> {
+        self.booster.as_ref()
+    }
+}
+
+impl Default for XgbClassifier {
+    fn default() -> Self {
+        XgbClassifier::new()
+    }
+}
+
+/// Gradient boosted regressor, minimising squared error (overriding any objective set in its parameters).
+pub struct XgbRegressor {
+    params: BoosterParameters,
+    boost_rounds: u32,
+    booster: Option<Booster>,
+}
+
+impl XgbRegressor {
+    /// Create a new regressor using default parameters and 100 boosting rounds.
+    pub fn new() -> Self {
+        XgbRegressor::with_params(BoosterParameters::default(), DEFAULT_BOOST_ROUNDS)
+    }
+
+    /// Create a new regressor using given parameters (apart from the objective) and number of boosting rounds.
+    pub fn with_params(params: BoosterParameters, boost_rounds: u32) -> Self {
+        XgbRegressor { params, boost_rounds, booster: None }
+    }
+
+    /// Train this regressor on `num_rows` rows of dense features `x`, with targets `y`, replacing any
+    /// previously trained model.
+    pub fn fit(&mut self, x: &[f32], num_rows: usize, y: &[f32]) -> XGBResult<()> {
+        let dtrain = training_matrix(x, num_rows, y)?;
+        self.booster = Some(train(&self.params, Objective::RegLinear, self.boost_rounds, &dtrain)?);
+        Ok(())
+    }
+
+    /// Predict the target of each of `num_rows` rows of dense features `x`.
+    pub fn predict(&self, x: &[f32], num_rows: usize) -> XGBResult<Vec<f32>> {
+        let booster = self.booster.as_ref().ok_or_else(not_fitted)?;
+        let dmat = prediction_matrix(booster, x, num_rows)?;
+        booster.predict(&dmat)
+    }
+
+    /// Get the trained model, or `None` if not fitted yet.
+    pub fn booster(&self) -> Option<&Booster> {
+        self.booster.as_ref()
+    }
+}
+
+impl Default for XgbRegressor {
+    fn default() -> Self {
+        XgbRegressor::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifier_binary() {
+        // two well separated clusters, with class 0 the majority
+        let mut x = Vec::new();
+        let mut y = Vec::new();
+        for i in 0..20 {
+            let class = if i < 14 { 0.0 } else { 1.0 };
+            let offset = (i % 3) as f32 * 0.1;
+            x.extend_from_slice(&[1.0 + class * 7.0 + offset, 2.0 + class * 7.0 - offset]);
+            y.push(class);
+        }
+
+        let mut clf = XgbClassifier::with_params(BoosterParameters::default(), 10);
+        assert_eq!(clf.predict(&x, 20).err(), Some(not_fitted()));
+        clf.fit(&x, 20, &y).unwrap();
+        assert_eq!(clf.num_classes(), 2);
+
+        assert_eq!(clf.predict(&[1.1, 2.1], 1).unwrap(), vec![0]);
+        let expected: Vec<u32> = y.iter().map(|label| *label as u32).collect();
+        assert_eq!(clf.predict(&x, 20).unwrap(), expected);
+
+        let (proba, shape) = clf.predict_proba(&x, 20).unwrap();
+        assert_eq!(shape, (20, 2));
+        for row in proba.chunks(2) {
+            assert!((row[0] + row[1] - 1.0).abs() < 1e-6);
+        }
+
+        // bad shapes
+        assert!(clf.predict(&[], 0).is_err());
+        assert_eq!(clf.predict(&x[..5], 2).err(), Some(XGBError::DimensionMismatch { expected: 4, got: 5 }));
+        assert_eq!(clf.predict(&x[..3], 1).err(), Some(XGBError::DimensionMismatch { expected: 2, got: 3 }));
+        assert!(clf.predict_proba(&x[..3], 1).is_err());
+        match clf.fit(&x[..39], 20, &y) {
+            Err(XGBError::InvalidParameter(_)) => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        y[0] = 0.5;
+        assert!(clf.fit(&x, 20, &y).is_err());
+    }
+
+    #[test]
+    fn regressor() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let y = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+
+        let mut reg = XgbRegressor::new();
+        reg.fit(&x, 6, &y).unwrap();
+        let preds = reg.predict(&x, 6).unwrap();
+        assert!(reg.predict(&x, 0).is_err());
+        for (pred, target) in preds.iter().zip(&y) {
+            assert!((pred - target).abs() < 0.5, "prediction {} too far from {}", pred, target);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Pure Rust representation of a trained tree model, parsed from XGBoost's JSON model dump.
+//!
+//! Useful for inspecting the structure of individual trees, or for evaluating a model without calling into
+//! XGBoost at all (e.g. to ship a small inference engine to devices without the XGBoost library).
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::{XGBResult, XGBError};
+
+/// Transformation applied to a model's raw margin to get its prediction, determined by the objective.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Link {
+    /// Margin is the prediction (e.g. `reg:squarederror`, `binary:logitraw`, ranking objectives).
+    Identity,
+
+    /// Prediction is the logistic sigmoid of the margin (e.g. `binary:logistic`).
+    Logistic,
+
+    /// Prediction is the exponential of the margin (e.g. `count:poisson`, `reg:gamma`, `reg:tweedie`).
+    Exp,
+}
+
+impl Link {
+    /// Get the link function of a single output objective, or `None` if it isn't supported.
+    pub(crate) fn from_objective(objective: &str) -> Option<Link> {
+        match objective {
+            "binary:logistic" | "reg:logistic"                              => Some(Link::Logistic),
+            "count:poisson" | "reg:gamma" | "reg:tweedie" | "survival:cox" => Some(Link::Exp),
+            objective if objective.starts_with("multi:")
+                || objective == "binary:hinge"
+                || objective == "survival:aft"                              => None,
+            _                                                               => Some(Link::Identity),
+        }
+    }
+
+    /// Transform a raw margin into a prediction.
+    pub(crate) fn apply(self, margin: f32) -> f32 {
+        match self {
+            Link::Identity => margin,
+            Link::Logistic => 1.0 / (1.0 + (-margin).exp()),
+            Link::Exp      => margin.exp(),
+        }
+    }
+
+    /// Transform a prediction (e.g. `base_score`) back into a raw margin.
+    pub(crate) fn inverse(self, value: f32) -> f32 {
+        match self {
+            Link::Identity => value,
+            Link::Logistic => (value / (1.0 - value)).ln(),
+            Link::Exp      => value.ln(),
+        }
+    }
+}
+
+/// Single node of a [`Tree`](struct.Tree.html).
+///
+/// Child nodes are referenced by their index in [`Tree::nodes`](struct.Tree.html#method.nodes).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    /// Internal node, going to `yes` if the value of `feature` is less than `threshold`, to `no` if not,
+    /// or to `missing` if the value is missing (NaN).
+    Split {
+        feature: usize,
+        threshold: f32,
+        yes: usize,
+        no: usize,
+        missing: usize,
+        /// Loss reduction from this split.
+        gain: f32,
+        /// Sum of the hessians of training instances reaching this node.
+        cover: f32,
+    },
+
+    /// Terminal node, contributing `value` to the margin of every instance reaching it.
+    Leaf {
+        value: f32,
+        /// Sum of the hessians of training instances reaching this node.
+        cover: f32,
+    },
+}
+
+/// Single decision tree, parsed from XGBoost's JSON dump of a tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    /// Parse a tree from one JSON document of [`Booster::dump_model_json`](struct.Booster.html#method.dump_model_json),
+    /// dumped with statistics.
+    ///
+    /// Split features are either named `fN` (or just `N`), where `N` is the feature index, or are looked up
+    /// by name in `feature_names` if the model has named features.
+    pub fn from_json(dump: &str, feature_names: &[String]) -> XGBResult<Self> {
+        let root: Value = serde_json::from_str(dump)
+            .map_err(|err| XGBError::new(format!("Unable to parse tree dump: {}", err)))?;
+        let feature_index: HashMap<&str, usize> = feature_names.iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+
+        let mut nodes = Vec::new();
+        Tree::add_node(&root, &feature_index, &mut nodes)?;
+        Ok(Tree { nodes })
+    }
+
+    /// Add `node` and all its descendants to `nodes`, returning the index `node` was added at.
+    fn add_node(node: &Value, feature_index: &HashMap<&str, usize>, nodes: &mut Vec<Node>) -> XGBResult<usize> {
+        let float = |name: &str| -> XGBResult<f32> {
+            node[name].as_f64()
+                .map(|value| value as f32)
+                .ok_or_else(|| XGBError::new(format!("Unable to parse '{}' from tree node: {}", name, node)))
+        };
+        let node_id = |name: &str| -> XGBResult<u64> {
+            node[name].as_u64()
+                .ok_or_else(|| XGBError::new(format!("Unable to parse '{}' from tree node: {}", name, node)))
+        };
+
+        let index = nodes.len();
+        if node.get("leaf").is_some() {
+            nodes.push(Node::Leaf { value: float("leaf")?, cover: float("cover").unwrap_or(0.0) });
+            return Ok(index);
+        }
+
+        let split = &node["split"];
+        let feature = match *split {
+            Value::Number(ref n) => n.as_u64().map(|n| n as usize),
+            Value::String(ref s) => feature_index.get(s.as_str()).cloned()
+                .or_else(|| s.trim_start_matches('f').parse::<usize>().ok()),
+            _                    => None,
+        };
+        let feature = match feature {
+            Some(feature) => feature,
+            None          => return Err(XGBError::new(format!("Unable to parse split feature: {}", split))),
+        };
+        let threshold = float("split_condition")
+            .map_err(|_| XGBError::new(format!("Unsupported (e.g. categorical) split in tree node: {}", node)))?;
+        let (yes_id, no_id, missing_id) = (node_id("yes")?, node_id("no")?, node_id("missing")?);
+
+        // reserve this node's index before its descendants are added, then fill it in once their indices are known
+        nodes.push(Node::Leaf { value: 0.0, cover: 0.0 });
+        let mut child_indices = HashMap::new();
+        if let Some(children) = node.get("children").and_then(|c| c.as_array()) {
+            for child in children {
+                let child_id = child["nodeid"].as_u64()
+                    .ok_or_else(|| XGBError::new(format!("Unable to parse 'nodeid' from tree node: {}", child)))?;
+                child_indices.insert(child_id, Tree::add_node(child, feature_index, nodes)?);
+            }
+        }
+        let child = |id: u64| -> XGBResult<usize> {
+            child_indices.get(&id)
+                .cloned()
+                .ok_or_else(|| XGBError::new(format!("Missing child node {} in tree node: {}", id, node)))
+        };
+
+        nodes[index] = Node::Split {
+            feature,
+            threshold,
+            yes: child(yes_id)?,
+            no: child(no_id)?,
+            missing: child(missing_id)?,
+            gain: float("gain").unwrap_or(0.0),
+            cover: float("cover").unwrap_or(0.0),
+        };
+        Ok(index)
+    }
+
+    /// Get the nodes of this tree, with the root node first.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Get the depth of this tree, i.e. the number of splits on the longest path from the root to a leaf
+    /// (so 0 for a tree with a single leaf).
+    pub fn depth(&self) -> u32 {
+        let mut max_depth = 0;
+        let mut stack = vec![(0, 0)];
+        while let Some((index, depth)) = stack.pop() {
+            match self.nodes[index] {
+                Node::Leaf { .. } => max_depth = max_depth.max(depth),
+                Node::Split { yes, no, missing, .. } => {
+                    stack.push((yes, depth + 1));
+                    stack.push((no, depth + 1));
+                    if missing != yes && missing != no {
+                        stack.push((missing, depth + 1));
+                    }
+                },
+            }
+        }
+        max_depth
+    }
+
+    /// Get the value of the leaf a dense row of features ends up at, treating NaN as missing.
+    ///
+    /// Features past the end of `row` are also treated as missing.
+    pub fn predict(&self, row: &[f32]) -> f32 {
+        let mut index = 0;
+        loop {
+            match self.nodes[index] {
+                Node::Leaf { value, .. } => return value,
+                Node::Split { feature, threshold, yes, no, missing, .. } => {
+                    index = match row.get(feature) {
+                        Some(value) if value.is_nan() => missing,
+                        Some(value) if *value < threshold => yes,
+                        Some(_) => no,
+                        None => missing,
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Collection of trees forming a single output model, evaluated entirely in Rust.
+///
+/// Created with [`Booster::to_forest`](struct.Booster.html#method.to_forest).
+#[derive(Clone, Debug)]
+pub struct Forest {
+    trees: Vec<Tree>,
+    base_margin: f32,
+    link: Link,
+}
+
+impl Forest {
+    pub(crate) fn new(trees: Vec<Tree>, base_score: f32, link: Link) -> Self {
+        Forest { trees, base_margin: link.inverse(base_score), link }
+    }
+
+    /// Get the trees in this forest, in the order they were boosted.
+    pub fn trees(&self) -> &[Tree] {
+        &self.trees
+    }
+
+    /// Predict the raw margin for a dense row of features (before any transformation by the objective),
+    /// treating NaN as missing.
+    pub fn predict_margin(&self, row: &[f32]) -> f32 {
+        self.base_margin + self.trees.iter().map(|tree| tree.predict(row)).sum::<f32>()
+    }
+
+    /// Predict the result for a dense row of features, treating NaN as missing.
+    ///
+    /// Should match [`Booster::predict`](struct.Booster.html#method.predict) up to floating point
+    /// rounding.
+    pub fn predict(&self, row: &[f32]) -> f32 {
+        self.link.apply(self.predict_margin(row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tree() {
+        let dump = r#"{ "nodeid": 0, "depth": 0, "split": "f2", "split_condition": 0.5, "yes": 3, "no": 1,
+                        "missing": 1, "gain": 10.0, "cover": 4.0, "children": [
+                          { "nodeid": 3, "leaf": -0.25, "cover": 1.0 },
+                          { "nodeid": 1, "leaf": 0.75, "cover": 3.0 }
+                        ]}"#;
+        let tree = Tree::from_json(dump, &[]).unwrap();
+        assert_eq!(tree.nodes().len(), 3);
+        assert_eq!(tree.depth(), 1);
+        match tree.nodes()[0] {
+            Node::Split { feature, yes, no, missing, .. } => {
+                assert_eq!(feature, 2);
+                assert_eq!(tree.nodes()[yes], Node::Leaf { value: -0.25, cover: 1.0 });
+                assert_eq!(no, missing);
+            },
+            ref node => panic!("unexpected root node: {:?}", node),
+        }
+
+        assert_eq!(tree.predict(&[0.0, 0.0, 0.1]), -0.25);
+        assert_eq!(tree.predict(&[0.0, 0.0, 0.9]), 0.75);
+        assert_eq!(tree.predict(&[0.0, 0.0, f32::NAN]), 0.75);
+        assert_eq!(tree.predict(&[0.0]), 0.75);
+
+        let names = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        let named = Tree::from_json(&dump.replace("\"f2\"", "\"c\""), &names).unwrap();
+        assert_eq!(named, tree);
+    }
+
+    #[test]
+    fn link_inverse() {
+        for link in &[Link::Identity, Link::Logistic, Link::Exp] {
+            assert!((link.apply(link.inverse(0.25)) - 0.25).abs() < 1e-6);
+        }
+    }
+}
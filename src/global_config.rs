@@ -0,0 +1,167 @@
+//! Process wide configuration of the XGBoost library, shared by every `Booster` and `DMatrix`.
+//!
+//! See the [XGBoost documentation](https://xgboost.readthedocs.io/en/latest/parameter.html#global-configuration)
+//! for more details.
+use std::ffi::{self, CStr};
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::{self, Value};
+use xgboost_sys;
+
+use super::{XGBResult, XGBError};
+
+/// Global XGBoost configuration, applied with [`apply`](struct.GlobalConfig.html#method.apply).
+/// Created using [`GlobalConfigBuilder`](struct.GlobalConfigBuilder.html).
+///
+/// Options left as `None` keep their current value when applied.
+#[derive(Builder, Clone, Debug, Default)]
+#[builder(default)]
+pub struct GlobalConfig {
+    /// Verbosity of XGBoost's log messages, from 0 (silent) to 3 (debug).
+    ///
+    /// *default*: `None`
+    verbosity: Option<u32>,
+
+    /// Whether to allocate GPU memory through the pooled [RMM](https://github.com/rapidsai/rmm) allocator.
+    ///
+    /// Requires the XGBoost library to be built with RMM support (`USE_RMM`), otherwise applying this
+    /// configuration with `use_rmm` enabled returns an error.
+    ///
+    /// *default*: `None`
+    use_rmm: Option<bool>,
+}
+
+impl GlobalConfig {
+    pub fn verbosity(&self) -> Option<u32> {
+        self.verbosity
+    }
+
+    pub fn set_verbosity(&mut self, verbosity: Option<u32>) {
+        self.verbosity = verbosity;
+    }
+
+    pub fn use_rmm(&self) -> Option<bool> {
+        self.use_rmm
+    }
+
+    pub fn set_use_rmm(&mut self, use_rmm: Option<bool>) {
+        self.use_rmm = use_rmm;
+    }
+
+    /// Apply this configuration to the XGBoost library, for the whole process.
+    pub fn apply(&self) -> XGBResult<()> {
+        let mut config = serde_json::Map::new();
+        if let Some(verbosity) = self.verbosity {
+            config.insert("verbosity".to_owned(), Value::from(verbosity));
+        }
+        if let Some(use_rmm) = self.use_rmm {
+            if use_rmm && build_info()?["USE_RMM"].as_bool() != Some(true) {
+                let msg = "use_rmm requires XGBoost to be built with RMM support (USE_RMM)".to_owned();
+                return Err(XGBError::InvalidParameter(msg));
+            }
+            config.insert("use_rmm".to_owned(), Value::from(use_rmm));
+        }
+        if config.is_empty() {
+            return Ok(());
+        }
+
+        let config = ffi::CString::new(Value::Object(config).to_string()).unwrap();
+        xgb_call!(xgboost_sys::XGBSetGlobalConfig(config.as_ptr()))
+    }
+}
+
+static DEFAULT_SEED: AtomicU64 = AtomicU64::new(0);
+
+/// Set the random seed used by every model configured after this call, for reproducible scripts.
+///
+/// XGBoost has no process wide seed of its own, so this sets the default `seed` of
+/// [`LearningTaskParameters`](../parameters/learning/struct.LearningTaskParameters.html) instead. Parameters
+/// built before this call, or with an explicit seed, are unaffected.
+///
+/// Seeding alone doesn't guarantee identical models: multithreaded training may sum gradients in a different
+/// order between runs, and the `shotgun` linear updater is nondeterministic by design. Train with a single
+/// thread (e.g. `threads(Some(1))`) when results must be reproduced exactly.
+pub fn seed_everything(seed: u64) {
+    DEFAULT_SEED.store(seed, Ordering::SeqCst);
+}
+
+/// Get the seed set by [`seed_everything`](fn.seed_everything.html), or 0 if it hasn't been called.
+pub(crate) fn default_seed() -> u64 {
+    DEFAULT_SEED.load(Ordering::SeqCst)
+}
+
+/// Get the XGBoost library's current global configuration, as a JSON document.
+pub fn current() -> XGBResult<String> {
+    let mut out = ptr::null();
+    xgb_call!(xgboost_sys::XGBGetGlobalConfig(&mut out))?;
+    Ok(unsafe { CStr::from_ptr(out).to_str().unwrap().to_owned() })
+}
+
+/// Get the options the XGBoost library was built with (e.g. `USE_CUDA`, `USE_RMM`).
+fn build_info() -> XGBResult<Value> {
+    let mut out = ptr::null();
+    xgb_call!(xgboost_sys::XGBuildInfo(&mut out))?;
+    let info = unsafe { CStr::from_ptr(out).to_str().unwrap() };
+    serde_json::from_str(info).map_err(|err| XGBError::new(format!("Unable to parse build info: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn use_rmm_requires_rmm_build() {
+        let config = GlobalConfigBuilder::default()
+            .use_rmm(Some(true))
+            .build()
+            .unwrap();
+        if build_info().unwrap()["USE_RMM"].as_bool() == Some(true) {
+            assert!(config.apply().is_ok());
+        } else {
+            match config.apply() {
+                Err(XGBError::InvalidParameter(msg)) => assert!(msg.contains("RMM")),
+                result => panic!("unexpected result: {:?}", result),
+            }
+        }
+        assert!(current().unwrap().contains("use_rmm"));
+    }
+
+    #[test]
+    fn seed_everything_reproducible() {
+        use parameters::{self, learning, tree};
+        use {Booster, DMatrix};
+
+        seed_everything(17);
+        assert_eq!(learning::LearningTaskParameters::default().seed(), 17);
+
+        let dtrain = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let train = || {
+            let tree_params = tree::TreeBoosterParametersBuilder::default()
+                .tree_method(tree::TreeMethod::Hist)
+                .subsample(0.5)
+                .colsample_bytree(0.5)
+                .build()
+                .unwrap();
+            let booster_params = parameters::BoosterParametersBuilder::default()
+                .booster_type(parameters::BoosterType::Tree(tree_params))
+                .threads(Some(1))
+                .verbose(false)
+                .build()
+                .unwrap();
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .booster_params(booster_params)
+                .dtrain(&dtrain)
+                .boost_rounds(5)
+                .build()
+                .unwrap();
+            Booster::train(&training_params).unwrap().save_to_buffer("json").unwrap()
+        };
+        assert!(train() == train());
+    }
+
+    #[test]
+    fn apply_empty_config() {
+        assert!(GlobalConfig::default().apply().is_ok());
+    }
+}
@@ -0,0 +1,103 @@
+//! Mapping between string class labels and the class indices XGBoost trains on.
+use std::collections::HashMap;
+
+use super::{XGBResult, XGBError};
+
+/// Encodes string class labels as contiguous class indices `0, 1, ..., n - 1` (as `f32`, ready to use as
+/// labels with multiclass objectives), and decodes predicted indices back into labels.
+///
+/// Classes are numbered in the order they're first seen, so indices of known classes never change when new
+/// ones are added.
+///
+/// ```
+/// use xgboost::LabelEncoder;
+///
+/// let mut encoder = LabelEncoder::new();
+/// assert_eq!(encoder.fit_transform(&["cat", "dog", "cat"]), vec![0.0, 1.0, 0.0]);
+/// assert_eq!(encoder.inverse_transform(&[1.0, 0.0]).unwrap(), vec!["dog", "cat"]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LabelEncoder {
+    classes: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl LabelEncoder {
+    /// Create a new encoder, with no known classes.
+    pub fn new() -> Self {
+        LabelEncoder::default()
+    }
+
+    /// Encode given labels, adding any labels not seen before as new classes.
+    pub fn fit_transform(&mut self, labels: &[&str]) -> Vec<f32> {
+        labels.iter()
+            .map(|label| {
+                if let Some(index) = self.indices.get(*label) {
+                    return *index as f32;
+                }
+                let index = self.classes.len();
+                self.classes.push((*label).to_owned());
+                self.indices.insert((*label).to_owned(), index);
+                index as f32
+            })
+            .collect()
+    }
+
+    /// Encode given labels, returning an error if any of them isn't a known class.
+    pub fn transform(&self, labels: &[&str]) -> XGBResult<Vec<f32>> {
+        labels.iter()
+            .map(|label| {
+                self.indices.get(*label)
+                    .map(|index| *index as f32)
+                    .ok_or_else(|| XGBError::InvalidData(format!("unknown class label '{}'", label)))
+            })
+            .collect()
+    }
+
+    /// Decode class indices (e.g. predictions of a `multi:softmax` model) back into labels, returning an error
+    /// if any index isn't a known class.
+    pub fn inverse_transform(&self, indices: &[f32]) -> XGBResult<Vec<&str>> {
+        indices.iter()
+            .map(|index| {
+                let class = if *index >= 0.0 && index.fract() == 0.0 {
+                    self.classes.get(*index as usize)
+                } else {
+                    None
+                };
+                class.map(|class| class.as_str())
+                    .ok_or_else(|| XGBError::InvalidData(format!("unknown class index {}", index)))
+            })
+            .collect()
+    }
+
+    /// Get the known classes, in index order.
+    pub fn classes(&self) -> &[String] {
+        &self.classes
+    }
+
+    /// Get the number of known classes, e.g. for the `num_class` of a multiclass objective.
+    pub fn num_classes(&self) -> usize {
+        self.classes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode() {
+        let mut encoder = LabelEncoder::new();
+        let labels = ["dog", "cat", "bird", "cat", "dog"];
+        let encoded = encoder.fit_transform(&labels);
+        assert_eq!(encoded, vec![0.0, 1.0, 2.0, 1.0, 0.0]);
+        assert_eq!(encoder.num_classes(), 3);
+        assert_eq!(encoder.classes(), &["dog", "cat", "bird"]);
+
+        assert_eq!(encoder.inverse_transform(&encoded).unwrap(), labels.to_vec());
+        assert_eq!(encoder.transform(&["bird"]).unwrap(), vec![2.0]);
+        assert!(encoder.transform(&["fish"]).is_err());
+        assert!(encoder.inverse_transform(&[3.0]).is_err());
+        assert!(encoder.inverse_transform(&[0.5]).is_err());
+    }
+}
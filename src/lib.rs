@@ -64,6 +64,15 @@ extern crate xgboost_sys;
 extern crate libc;
 extern crate tempfile;
 extern crate indexmap;
+extern crate serde_json;
+extern crate rand;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "sprs")]
+extern crate sprs;
 
 macro_rules! xgb_call {
     ($x:expr) => {
@@ -74,9 +83,24 @@ macro_rules! xgb_call {
 mod error;
 pub use error::{XGBResult, XGBError};
 
+mod array_interface;
+
 mod dmatrix;
-pub use dmatrix::DMatrix;
+pub use dmatrix::{Batch, ColumnStat, DMatrix};
 
 mod booster;
-pub use booster::{Booster, FeatureMap, FeatureType};
+pub use booster::{Booster, FeatureMap, FeatureStat, FeatureType, ImportanceType, IterationReport, TaskType};
+
+mod model_bytes;
+pub use model_bytes::ModelBytes;
+
+mod label_encoder;
+pub use label_encoder::LabelEncoder;
+
+mod forest;
+pub use forest::{Forest, Node, Tree};
+
 pub mod parameters;
+pub mod global_config;
+pub mod estimators;
+pub use global_config::seed_everything;
@@ -0,0 +1,112 @@
+//! Serialised form of a Booster, for storing models in keyed stores or nesting inside other structs.
+
+use std::convert::TryFrom;
+
+use super::{Booster, XGBResult, XGBError};
+
+/// A [`Booster`](struct.Booster.html) serialised as bytes, using XGBoost's
+/// [UBJSON](https://ubjson.org/) model format.
+///
+/// Converted to and from a `Booster` with `TryFrom`/`TryInto`. When the `serde` feature is enabled, also
+/// implements `Serialize` and `Deserialize`, so models can be stored inside other serialisable types.
+///
+/// ```should_panic
+/// use std::convert::TryFrom;
+/// use xgboost::{Booster, ModelBytes};
+///
+/// let bst = Booster::load("somefile.bin").unwrap();
+/// let bytes = ModelBytes::try_from(&bst).unwrap();
+/// let bst = Booster::try_from(bytes).unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ModelBytes(pub Vec<u8>);
+
+impl ModelBytes {
+    /// Get the raw serialised model.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Consume this instance, returning the raw serialised model.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<Vec<u8>> for ModelBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        ModelBytes(bytes)
+    }
+}
+
+impl AsRef<[u8]> for ModelBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> TryFrom<&'a Booster> for ModelBytes {
+    type Error = XGBError;
+
+    fn try_from(booster: &'a Booster) -> XGBResult<Self> {
+        Ok(ModelBytes(booster.save_to_buffer("ubj")?))
+    }
+}
+
+impl TryFrom<ModelBytes> for Booster {
+    type Error = XGBError;
+
+    fn try_from(bytes: ModelBytes) -> XGBResult<Self> {
+        Booster::load_buffer(&bytes.0)
+    }
+}
+
+impl<'a> TryFrom<&'a ModelBytes> for Booster {
+    type Error = XGBError;
+
+    fn try_from(bytes: &'a ModelBytes) -> XGBResult<Self> {
+        Booster::load_buffer(&bytes.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DMatrix;
+
+    fn train_booster(dmat: &DMatrix) -> Booster {
+        let mut booster = Booster::new_with_cached_dmats(&Default::default(), &[dmat]).unwrap();
+        for i in 0..3 {
+            booster.update(dmat, i).expect("update failed");
+        }
+        booster
+    }
+
+    #[test]
+    fn round_trip() {
+        let dmat = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let booster = train_booster(&dmat);
+        let expected = booster.predict(&dmat).unwrap();
+
+        let bytes = ModelBytes::try_from(&booster).unwrap();
+        drop(booster);
+        let booster = Booster::try_from(bytes).unwrap();
+        assert_eq!(booster.predict(&dmat).unwrap(), expected);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use serde_json;
+
+        let dmat = DMatrix::load("xgboost-sys/xgboost/demo/data/agaricus.txt.train").unwrap();
+        let booster = train_booster(&dmat);
+        let expected = booster.predict(&dmat).unwrap();
+
+        let serialised = serde_json::to_string(&ModelBytes::try_from(&booster).unwrap()).unwrap();
+        let bytes: ModelBytes = serde_json::from_str(&serialised).unwrap();
+        let booster = Booster::try_from(&bytes).unwrap();
+        assert_eq!(booster.predict(&dmat).unwrap(), expected);
+    }
+}
@@ -4,6 +4,7 @@
 use std;
 use std::default::Default;
 
+use global_config;
 use super::Interval;
 
 /// Learning objective used when training a booster model.
@@ -32,8 +33,13 @@ pub enum Objective {
     /// GPU version of [`RegBinaryLogisticRaw`](#variant.RegBinaryLogisticRaw).
     GpuBinaryLogisticRaw,
 
-    /// Poisson regression for count data, outputs mean of poisson distribution.
-    CountPoisson,
+    /// Poisson regression for count data, outputs mean of poisson distribution. Takes an optional
+    /// **max delta step** parameter, which is used to safeguard optimization.
+    ///
+    /// Set to `None` to use XGBoost's default (currently `0.7`). XGBoost shares the `max_delta_step` parameter
+    /// with the tree booster, so a tree booster's `max_delta_step` (if set to anything but its default of `0`)
+    /// also applies here when this is `None`, and can't be combined with `Some`.
+    CountPoisson(Option<f32>),
 
     /// Cox regression for right censored survival time data (negative values are considered right
     /// censored).
@@ -53,6 +59,9 @@ pub enum Objective {
     /// Ranking task which minimises pairwise loss.
     RankPairwise,
 
+    /// Ranking task which directly optimises Normalized Discounted Cumulative Gain (NDCG) using LambdaMART.
+    RankNdcg,
+
     /// Gamma regression with log-link. Output is the mean of the gamma distribution.
     RegGamma,
 
@@ -62,10 +71,16 @@ pub enum Objective {
     /// * Set closer to 2 to shift towards a gamma distribution
     /// * Set closer to 1 to shift towards a Poisson distribution
     ///
-    /// *range*: (1, 2)
+    /// *range*: (1, 2), exclusive
     ///
     /// Set to `None` to use XGBoost's default (currently `1.5`).
     RegTweedie(Option<f32>),
+
+    /// Quantile regression (pinball loss), predicting one output per quantile given in the learning task's
+    /// `quantile_alpha` parameter.
+    ///
+    /// Requires XGBoost 2.0 or later.
+    RegQuantileError,
 }
 
 impl Copy for Objective {}
@@ -85,13 +100,15 @@ impl ToString for Objective {
             Objective::GpuRegLogistic => "gpu:reg:logistic".to_owned(),
             Objective::GpuBinaryLogistic => "gpu:binary:logistic".to_owned(),
             Objective::GpuBinaryLogisticRaw => "gpu:binary:logitraw".to_owned(),
-            Objective::CountPoisson => "count:poisson".to_owned(),
+            Objective::CountPoisson(_) => "count:poisson".to_owned(),
             Objective::SurvivalCox => "survival:cox".to_owned(),
             Objective::MultiSoftmax(_) => "multi:softmax".to_owned(), // num_class conf must also be set
             Objective::MultiSoftprob(_) => "multi:softprob".to_owned(), // num_class conf must also be set
             Objective::RankPairwise => "rank:pairwise".to_owned(),
+            Objective::RankNdcg => "rank:ndcg".to_owned(),
             Objective::RegGamma => "reg:gamma".to_owned(),
             Objective::RegTweedie(_) => "reg:tweedie".to_owned(),
+            Objective::RegQuantileError => "reg:quantileerror".to_owned(),
         }
     }
 }
@@ -237,8 +254,26 @@ pub struct LearningTaskParameters {
 
     /// Random seed.
     ///
-    /// *default*: 0
+    /// *default*: 0, or the seed set by [`seed_everything`](../../global_config/fn.seed_everything.html)
     seed: u64,
+
+    /// Quantiles to predict with the [`RegQuantileError`](enum.Objective.html#variant.RegQuantileError)
+    /// objective, each in the range (0, 1). Predictions have one column per quantile, in the given order.
+    ///
+    /// *default*: empty
+    quantile_alpha: Vec<f32>,
+
+    /// Gain of each relevance grade used by NDCG when ranking, i.e. `label_gain[i]` is the gain of
+    /// an instance labelled with grade `i`. Labels must then be integers less than `label_gain.len()`.
+    ///
+    /// *default*: empty (i.e. a gain of `2^i - 1` for grade `i`)
+    label_gain: Vec<f32>,
+
+    /// Whether to skip the objective's default evaluation metric, e.g. when only a custom evaluation function
+    /// is wanted. Metrics set explicitly in `eval_metrics` are still evaluated.
+    ///
+    /// *default*: `false`
+    disable_default_eval_metric: bool,
 }
 
 impl Default for LearningTaskParameters {
@@ -247,7 +282,10 @@ impl Default for LearningTaskParameters {
             objective: Objective::default(),
             base_score: 0.5,
             eval_metrics: Metrics::Auto,
-            seed: 0,
+            seed: global_config::default_seed(),
+            quantile_alpha: Vec::new(),
+            label_gain: Vec::new(),
+            disable_default_eval_metric: false,
         }
     }
 }
@@ -285,6 +323,30 @@ impl LearningTaskParameters {
         self.seed = seed;
     }
 
+    pub fn quantile_alpha(&self) -> &[f32] {
+        &self.quantile_alpha
+    }
+
+    pub fn set_quantile_alpha(&mut self, quantile_alpha: Vec<f32>) {
+        self.quantile_alpha = quantile_alpha;
+    }
+
+    pub fn label_gain(&self) -> &[f32] {
+        &self.label_gain
+    }
+
+    pub fn set_label_gain(&mut self, label_gain: Vec<f32>) {
+        self.label_gain = label_gain;
+    }
+
+    pub fn disable_default_eval_metric(&self) -> bool {
+        self.disable_default_eval_metric
+    }
+
+    pub fn set_disable_default_eval_metric(&mut self, disable_default_eval_metric: bool) {
+        self.disable_default_eval_metric = disable_default_eval_metric;
+    }
+
     pub(crate) fn as_string_pairs(&self) -> Vec<(String, String)> {
         let mut v = Vec::new();
 
@@ -294,12 +356,28 @@ impl LearningTaskParameters {
             v.push(("num_class".to_owned(), n.to_string()));
         } else if let Objective::RegTweedie(Some(n)) = self.objective {
             v.push(("tweedie_variance_power".to_owned(), n.to_string()));
+        } else if let Objective::CountPoisson(Some(n)) = self.objective {
+            v.push(("max_delta_step".to_owned(), n.to_string()));
         }
 
         v.push(("objective".to_owned(), self.objective.to_string()));
         v.push(("base_score".to_owned(), self.base_score.to_string()));
         v.push(("seed".to_owned(), self.seed.to_string()));
 
+        if !self.quantile_alpha.is_empty() {
+            let alphas: Vec<String> = self.quantile_alpha.iter().map(|alpha| alpha.to_string()).collect();
+            v.push(("quantile_alpha".to_owned(), format!("[{}]", alphas.join(","))));
+        }
+
+        if !self.label_gain.is_empty() {
+            let gains: Vec<String> = self.label_gain.iter().map(|gain| gain.to_string()).collect();
+            v.push(("label_gain".to_owned(), gains.join(",")));
+        }
+
+        if self.disable_default_eval_metric {
+            v.push(("disable_default_eval_metric".to_owned(), "1".to_owned()));
+        }
+
         if let Metrics::Custom(eval_metrics) = &self.eval_metrics {
             for metric in eval_metrics {
                 v.push(("eval_metric".to_owned(), metric.to_string()));
@@ -313,8 +391,99 @@ impl LearningTaskParameters {
 impl LearningTaskParametersBuilder {
     fn validate(&self) -> Result<(), String> {
         if let Some(Objective::RegTweedie(variance_power)) = self.objective {
-            Interval::new_closed_closed(1.0, 2.0).validate(&variance_power, "tweedie_variance_power")?;
+            Interval::new_open_open(1.0, 2.0).validate(&variance_power, "tweedie_variance_power")?;
+        } else if let Some(Objective::CountPoisson(max_delta_step)) = self.objective {
+            Interval::new_closed_closed(0.0, f32::INFINITY).validate(&max_delta_step, "max_delta_step")?;
+        } else if let Some(Objective::RegQuantileError) = self.objective {
+            match self.quantile_alpha {
+                Some(ref alphas) if !alphas.is_empty() => for alpha in alphas {
+                    Interval::new_open_open(0.0, 1.0).validate(&Some(*alpha), "quantile_alpha")?;
+                },
+                _ => return Err("quantile_alpha must be set for the reg:quantileerror objective".to_owned()),
+            }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantile_params() {
+        let p = LearningTaskParametersBuilder::default()
+            .objective(Objective::RegQuantileError)
+            .quantile_alpha(vec![0.1, 0.9])
+            .build()
+            .unwrap();
+        let pairs = p.as_string_pairs();
+        assert!(pairs.contains(&("objective".to_owned(), "reg:quantileerror".to_owned())));
+        assert!(pairs.contains(&("quantile_alpha".to_owned(), "[0.1,0.9]".to_owned())));
+
+        let result = LearningTaskParametersBuilder::default()
+            .objective(Objective::RegQuantileError)
+            .build();
+        assert!(result.is_err());
+
+        let result = LearningTaskParametersBuilder::default()
+            .objective(Objective::RegQuantileError)
+            .quantile_alpha(vec![0.5, 1.5])
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn label_gain_params() {
+        let p = LearningTaskParametersBuilder::default()
+            .objective(Objective::RankPairwise)
+            .label_gain(vec![0.0, 1.0, 3.0, 7.0])
+            .build()
+            .unwrap();
+        let pairs = p.as_string_pairs();
+        assert!(pairs.contains(&("label_gain".to_owned(), "0,1,3,7".to_owned())));
+
+        let p = LearningTaskParametersBuilder::default().build().unwrap();
+        assert!(!p.as_string_pairs().iter().any(|(k, _)| k == "label_gain"));
+    }
+
+    #[test]
+    fn tweedie_params() {
+        let p = LearningTaskParametersBuilder::default()
+            .objective(Objective::RegTweedie(Some(1.5)))
+            .build()
+            .unwrap();
+        let pairs = p.as_string_pairs();
+        assert!(pairs.contains(&("objective".to_owned(), "reg:tweedie".to_owned())));
+        assert!(pairs.contains(&("tweedie_variance_power".to_owned(), "1.5".to_owned())));
+        assert!(!pairs.iter().any(|(k, _)| k == "max_delta_step"));
+
+        assert!(LearningTaskParametersBuilder::default()
+            .objective(Objective::RegTweedie(Some(2.0)))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn poisson_params() {
+        let p = LearningTaskParametersBuilder::default()
+            .objective(Objective::CountPoisson(Some(0.5)))
+            .build()
+            .unwrap();
+        let pairs = p.as_string_pairs();
+        assert!(pairs.contains(&("objective".to_owned(), "count:poisson".to_owned())));
+        assert!(pairs.contains(&("max_delta_step".to_owned(), "0.5".to_owned())));
+        assert!(!pairs.iter().any(|(k, _)| k == "tweedie_variance_power"));
+
+        let p = LearningTaskParametersBuilder::default()
+            .objective(Objective::CountPoisson(None))
+            .build()
+            .unwrap();
+        assert!(!p.as_string_pairs().iter().any(|(k, _)| k == "max_delta_step"));
+
+        assert!(LearningTaskParametersBuilder::default()
+            .objective(Objective::CountPoisson(Some(-1.0)))
+            .build()
+            .is_err());
+    }
+}
@@ -8,6 +8,8 @@
 //! any given settings are valid when built.
 use std::default::Default;
 use std::fmt::{self, Display};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
 pub mod tree;
 pub mod learning;
@@ -17,11 +19,35 @@ mod booster;
 
 use super::DMatrix;
 pub use self::booster::BoosterType;
-use super::booster::CustomObjective;
+use super::booster::{CustomObjective, IterationReport};
+
+/// Device used by XGBoost for training and predicting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Device {
+    /// Run on the CPU.
+    Cpu,
+
+    /// Run on the CUDA GPU with given ordinal (requires XGBoost to be built with CUDA support).
+    Cuda(u32),
+}
+
+impl Default for Device {
+    fn default() -> Self { Device::Cpu }
+}
+
+impl Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Device::Cpu           => write!(f, "cpu"),
+            Device::Cuda(ordinal) => write!(f, "cuda:{}", ordinal),
+        }
+    }
+}
 
 /// Parameters for training boosters.
 /// Created using [`BoosterParametersBuilder`](struct.BoosterParametersBuilder.html).
 #[derive(Builder, Clone)]
+#[builder(build_fn(validate = "Self::validate"))]
 #[builder(default)]
 pub struct BoosterParameters {
     /// Type of booster (tree, linear or DART) along with its parameters.
@@ -41,6 +67,11 @@ pub struct BoosterParameters {
     ///
     /// *default*: `None` (XGBoost will automatically determing max threads to use)
     threads: Option<u32>,
+
+    /// Device to run on, replacing XGBoost's deprecated `gpu_id` parameter (requires XGBoost 2.0).
+    ///
+    /// *default*: `None` (XGBoost's default, i.e. the CPU)
+    device: Option<Device>,
 }
 
 impl Default for BoosterParameters {
@@ -50,6 +81,7 @@ impl Default for BoosterParameters {
             learning_params: learning::LearningTaskParameters::default(),
             verbose: false,
             threads: None,
+            device: None,
         }
     }
 }
@@ -99,6 +131,18 @@ impl BoosterParameters {
         self.threads = threads.into();
     }
 
+    /// Get device XGBoost will run on, if set.
+    pub fn device(&self) -> Option<Device> {
+        self.device
+    }
+
+    /// Set device XGBoost will run on.
+    ///
+    /// If `None`, XGBoost will use its default device (the CPU).
+    pub fn set_device<T: Into<Option<Device>>>(&mut self, device: T) {
+        self.device = device.into();
+    }
+
     pub(crate) fn as_string_pairs(&self) -> Vec<(String, String)> {
         let mut v = Vec::new();
 
@@ -111,10 +155,35 @@ impl BoosterParameters {
             v.push(("nthread".to_owned(), nthread.to_string()));
         }
 
+        if let Some(device) = self.device {
+            v.push(("device".to_owned(), device.to_string()));
+        }
+
         v
     }
 }
 
+impl BoosterParametersBuilder {
+    fn validate(&self) -> Result<(), String> {
+        // XGBoost stores device ordinals as signed ints, treating negative values as the CPU
+        if let Some(Some(Device::Cuda(ordinal))) = self.device {
+            Interval::new_closed_closed(0, i32::MAX as u32).validate(&Some(ordinal), "device")?;
+        }
+
+        // XGBoost reads a single max_delta_step for both the tree booster and the count:poisson objective
+        if let (Some(BoosterType::Tree(ref tree_params)), Some(ref learning_params)) =
+            (&self.booster_type, &self.learning_params) {
+            if let learning::Objective::CountPoisson(Some(_)) = learning_params.objective {
+                if tree_params.max_delta_step() != 0.0 {
+                    return Err("max_delta_step can't be set on both the tree booster and the count:poisson \
+                                objective".to_owned());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 type CustomEvaluation = fn(&[f32], &DMatrix) -> f32;
 
 /// Parameters used by the [`Booster::train`](../struct.Booster.html#method.train) method for training new models.
@@ -156,7 +225,65 @@ pub struct TrainingParameters<'a> {
     /// *default*: `None`
     #[builder(default="None")]
     pub(crate) custom_evaluation_fn: Option<CustomEvaluation>,
-    // TODO: callbacks
+
+    /// Optional function called after each boosting round with a structured report of that round's
+    /// evaluation results, e.g. to drive progress displays.
+    ///
+    /// *default*: `None`
+    #[builder(default="None")]
+    pub(crate) progress_fn: Option<&'a dyn Fn(&IterationReport)>,
+
+    /// Optional number of rounds without improvement after which to stop training early.
+    ///
+    /// Requires `evaluation_sets`, and uses the last metric of the last evaluation set to decide whether the
+    /// model is still improving. Metrics are minimised, except for those which XGBoost maximises (e.g. `auc`,
+    /// `map` and `ndcg`). After stopping early, the trained Booster's predictions default to only using trees
+    /// up to the best iteration.
+    ///
+    /// *default*: `None`
+    #[builder(default="None")]
+    pub(crate) early_stopping_rounds: Option<u32>,
+
+    /// How often to report evaluation results during training, as `Some(n)` to report every `n`th round
+    /// (starting with the first), or `None` (or `Some(0)`) to train silently.
+    ///
+    /// Results are logged at info level, and also printed to stdout if `print_eval` is set.
+    ///
+    /// *default*: `Some(1)`
+    #[builder(default="Some(1)")]
+    pub(crate) verbose_eval: Option<u32>,
+
+    /// Whether to also print the evaluation results reported according to `verbose_eval` to stdout, e.g. for
+    /// command line tools without a logger set up.
+    ///
+    /// *default*: `false`
+    #[builder(default="false")]
+    pub(crate) print_eval: bool,
+
+    /// Whether early stopping should treat higher scores of its metric as better (`Some(true)`) or lower
+    /// scores (`Some(false)`).
+    ///
+    /// Overrides the direction guessed from the metric's name, which is needed for custom metrics where
+    /// higher is better (these are otherwise minimised).
+    ///
+    /// *default*: `None`
+    #[builder(default="None")]
+    pub(crate) early_stopping_maximize: Option<bool>,
+
+    /// Whether to fail with an `XGBError::InvalidParameter` error, instead of logging a warning, when an
+    /// evaluation metric doesn't suit the learning objective (e.g. `auc` with `reg:squarederror`).
+    ///
+    /// *default*: `false`
+    #[builder(default="false")]
+    pub(crate) strict_metrics: bool,
+
+    /// Optional flag to cancel training, e.g. from another thread or from `progress_fn`.
+    ///
+    /// Checked before each boosting round. Once set, training stops and returns the model trained so far.
+    ///
+    /// *default*: `None`
+    #[builder(default="None")]
+    pub(crate) cancel: Option<Arc<AtomicBool>>,
 }
 
 impl <'a> TrainingParameters<'a> {
@@ -207,6 +334,62 @@ impl <'a> TrainingParameters<'a> {
     pub fn set_custom_evaluation_fn(&mut self, custom_evaluation_fn: Option<CustomEvaluation>) {
         self.custom_evaluation_fn = custom_evaluation_fn;
     }
+
+    pub fn progress_fn(&self) -> &Option<&'a dyn Fn(&IterationReport)> {
+        &self.progress_fn
+    }
+
+    pub fn set_progress_fn(&mut self, progress_fn: Option<&'a dyn Fn(&IterationReport)>) {
+        self.progress_fn = progress_fn;
+    }
+
+    pub fn early_stopping_rounds(&self) -> Option<u32> {
+        self.early_stopping_rounds
+    }
+
+    pub fn set_early_stopping_rounds(&mut self, early_stopping_rounds: Option<u32>) {
+        self.early_stopping_rounds = early_stopping_rounds;
+    }
+
+    pub fn verbose_eval(&self) -> Option<u32> {
+        self.verbose_eval
+    }
+
+    pub fn set_verbose_eval(&mut self, verbose_eval: Option<u32>) {
+        self.verbose_eval = verbose_eval;
+    }
+
+    pub fn print_eval(&self) -> bool {
+        self.print_eval
+    }
+
+    pub fn set_print_eval(&mut self, print_eval: bool) {
+        self.print_eval = print_eval;
+    }
+
+    pub fn early_stopping_maximize(&self) -> Option<bool> {
+        self.early_stopping_maximize
+    }
+
+    pub fn set_early_stopping_maximize(&mut self, early_stopping_maximize: Option<bool>) {
+        self.early_stopping_maximize = early_stopping_maximize;
+    }
+
+    pub fn strict_metrics(&self) -> bool {
+        self.strict_metrics
+    }
+
+    pub fn set_strict_metrics(&mut self, strict_metrics: bool) {
+        self.strict_metrics = strict_metrics;
+    }
+
+    pub fn cancel(&self) -> &Option<Arc<AtomicBool>> {
+        &self.cancel
+    }
+
+    pub fn set_cancel(&mut self, cancel: Option<Arc<AtomicBool>>) {
+        self.cancel = cancel;
+    }
 }
 
 enum Inclusion {
@@ -277,3 +460,75 @@ impl<T: PartialOrd + Display> Interval<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_params() {
+        let p = BoosterParametersBuilder::default()
+            .device(Some(Device::Cuda(0)))
+            .build()
+            .unwrap();
+        assert!(p.as_string_pairs().contains(&("device".to_owned(), "cuda:0".to_owned())));
+
+        let p = BoosterParametersBuilder::default()
+            .device(Some(Device::Cpu))
+            .build()
+            .unwrap();
+        assert!(p.as_string_pairs().contains(&("device".to_owned(), "cpu".to_owned())));
+
+        let p = BoosterParametersBuilder::default().build().unwrap();
+        assert!(!p.as_string_pairs().iter().any(|(k, _)| k == "device"));
+
+        let result = BoosterParametersBuilder::default()
+            .device(Some(Device::Cuda(u32::MAX)))
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn poisson_max_delta_step() {
+        let max_delta_steps = |p: &BoosterParameters| -> Vec<String> {
+            p.as_string_pairs().into_iter().filter(|(k, _)| k == "max_delta_step").map(|(_, v)| v).collect()
+        };
+        let poisson = |max_delta_step: Option<f32>| {
+            learning::LearningTaskParametersBuilder::default()
+                .objective(learning::Objective::CountPoisson(max_delta_step))
+                .build()
+                .unwrap()
+        };
+        let tree_params = |max_delta_step: f32| {
+            BoosterType::Tree(tree::TreeBoosterParametersBuilder::default()
+                              .max_delta_step(max_delta_step)
+                              .build()
+                              .unwrap())
+        };
+
+        let p = BoosterParametersBuilder::default()
+            .learning_params(poisson(Some(0.5)))
+            .build()
+            .unwrap();
+        assert_eq!(max_delta_steps(&p), vec!["0.5"]);
+
+        let p = BoosterParametersBuilder::default()
+            .learning_params(poisson(None))
+            .build()
+            .unwrap();
+        assert!(max_delta_steps(&p).is_empty());
+
+        let p = BoosterParametersBuilder::default()
+            .booster_type(tree_params(2.0))
+            .learning_params(poisson(None))
+            .build()
+            .unwrap();
+        assert_eq!(max_delta_steps(&p), vec!["2"]);
+
+        let result = BoosterParametersBuilder::default()
+            .booster_type(tree_params(2.0))
+            .learning_params(poisson(Some(0.5)))
+            .build();
+        assert!(result.is_err());
+    }
+}
@@ -175,19 +175,48 @@ impl Default for GrowPolicy {
     fn default() -> Self { GrowPolicy::Depthwise }
 }
 
+/// Method used to sample training instances when `subsample` is less than 1.
+#[derive(Clone)]
+pub enum SamplingMethod {
+    /// Each training instance has an equal probability of being selected.
+    Uniform,
+
+    /// Selection probability of each training instance is proportional to the regularized absolute value
+    /// of its gradients, so `subsample` can be set as low as 0.1 without losing accuracy. Only supported
+    /// when training on GPU (e.g. with [`TreeMethod::GpuHist`](enum.TreeMethod.html#variant.GpuHist)).
+    GradientBased,
+}
+
+impl ToString for SamplingMethod {
+    fn to_string(&self) -> String {
+        match *self {
+            SamplingMethod::Uniform => "uniform".to_owned(),
+            SamplingMethod::GradientBased => "gradient_based".to_owned(),
+        }
+    }
+}
+
 /// The type of predictor algorithm to use. Provides the same results but allows the use of GPU or CPU.
+///
+/// Can also be changed on an already trained (or loaded) model with
+/// [`Booster::set_predictor`](../../struct.Booster.html#method.set_predictor), e.g. to serve a model trained
+/// on GPU from a machine without one.
 #[derive(Clone)]
 pub enum Predictor {
-    /// Multicore CPU prediction algorithm.
+    /// Let XGBoost choose the predictor based on the tree method (`auto`).
+    Auto,
+
+    /// Multicore CPU prediction algorithm (`cpu_predictor`).
     Cpu,
 
-    /// Prediction using GPU. Default for ‘gpu_exact’ and ‘gpu_hist’ tree method.
+    /// Prediction using GPU (`gpu_predictor`). Default for ‘gpu_exact’ and ‘gpu_hist’ tree method.
     Gpu,
 }
 
 impl ToString for Predictor {
     fn to_string(&self) -> String {
         match *self {
+            Predictor::Auto => "auto".to_owned(),
             Predictor::Cpu => "cpu_predictor".to_owned(),
             Predictor::Gpu => "gpu_predictor".to_owned(),
         }
@@ -213,7 +242,7 @@ pub struct TreeBoosterParameters {
     eta: f32,
 
     /// Minimum loss reduction required to make a further partition on a leaf node of the tree.
-    /// The larger, the more conservative the algorithm will be.
+    /// The larger, the more conservative the algorithm will be. Also known as `min_split_loss`.
     ///
     /// * range: [0,∞]
     /// * default: 0
@@ -271,13 +300,17 @@ pub struct TreeBoosterParameters {
     /// * default: 1.0
     colsample_bynode: f32,
 
-    /// L2 regularization term on weights, increase this value will make model more conservative.
+    /// L2 regularization term on weights, increase this value will make model more conservative. Also known
+    /// as `reg_lambda`.
     ///
+    /// * range: [0,∞]
     /// * default: 1
     lambda: f32,
 
-    /// L1 regularization term on weights, increase this value will make model more conservative.
+    /// L1 regularization term on weights, increase this value will make model more conservative. Also known
+    /// as `reg_alpha`.
     ///
+    /// * range: [0,∞]
     /// * default: 0
     alpha: f32,
 
@@ -341,6 +374,33 @@ pub struct TreeBoosterParameters {
     ///
     /// * default: [`Predictor::Cpu`](enum.Predictor.html#variant.Cpu)
     predictor: Predictor,
+
+    /// Only used for categorical features (with the `hist` or `approx` tree methods).
+    /// Categorical features with fewer categories than this are split one-hot style (one category against
+    /// the rest), otherwise categories are partitioned into two groups.
+    ///
+    /// * default: `None` (use XGBoost's default)
+    max_cat_to_onehot: Option<u32>,
+
+    /// Only used for partition-based categorical splits.
+    /// Maximum number of categories considered for each split, used to prevent overfitting.
+    ///
+    /// * default: `None` (use XGBoost's default)
+    max_cat_threshold: Option<u32>,
+
+    /// Method used to sample training instances when `subsample` is less than 1.
+    ///
+    /// * default: `None` (use XGBoost's default, i.e. [`SamplingMethod::Uniform`](enum.SamplingMethod.html#variant.Uniform))
+    sampling_method: Option<SamplingMethod>,
+
+    /// Only used by the `hist` tree method (including on GPU).
+    /// Maximum number of cached node histograms. Lowering this reduces memory usage when training deep
+    /// trees on wide data, at the cost of recomputing histograms that no longer fit in the cache (slower
+    /// training).
+    ///
+    /// * range: [1,∞]
+    /// * default: `None` (use XGBoost's default)
+    max_cached_hist_node: Option<u32>,
 }
 
 impl Default for TreeBoosterParameters {
@@ -368,27 +428,53 @@ impl Default for TreeBoosterParameters {
             max_bin: 256,
             num_parallel_tree: 1,
             predictor: Predictor::default(),
+            max_cat_to_onehot: None,
+            max_cat_threshold: None,
+            sampling_method: None,
+            max_cached_hist_node: None,
         }
     }
 }
 
 impl TreeBoosterParameters {
+    pub(crate) fn max_delta_step(&self) -> f32 {
+        self.max_delta_step
+    }
+
     pub(crate) fn as_string_pairs(&self) -> Vec<(String, String)> {
         let mut v = Vec::new();
 
         v.push(("booster".to_owned(), "gbtree".to_owned()));
 
+        // only pass regularization parameters which differ from XGBoost's defaults, leaving the rest out so
+        // XGBoost applies its own defaults
+        let defaults = TreeBoosterParameters::default();
+
         v.push(("eta".to_owned(), self.eta.to_string()));
-        v.push(("gamma".to_owned(), self.gamma.to_string()));
-        v.push(("max_depth".to_owned(), self.max_depth.to_string()));
-        v.push(("min_child_weight".to_owned(), self.min_child_weight.to_string()));
-        v.push(("max_delta_step".to_owned(), self.max_delta_step.to_string()));
+        if self.gamma != defaults.gamma {
+            v.push(("gamma".to_owned(), self.gamma.to_string()));
+        }
+        if self.max_depth != defaults.max_depth {
+            v.push(("max_depth".to_owned(), self.max_depth.to_string()));
+        }
+        if self.min_child_weight != defaults.min_child_weight {
+            v.push(("min_child_weight".to_owned(), self.min_child_weight.to_string()));
+        }
+        // XGBoost shares this key with the count:poisson objective's own max_delta_step (which has a different
+        // default), so only pass it when set
+        if self.max_delta_step != defaults.max_delta_step {
+            v.push(("max_delta_step".to_owned(), self.max_delta_step.to_string()));
+        }
         v.push(("subsample".to_owned(), self.subsample.to_string()));
         v.push(("colsample_bytree".to_owned(), self.colsample_bytree.to_string()));
         v.push(("colsample_bylevel".to_owned(), self.colsample_bylevel.to_string()));
         v.push(("colsample_bynode".to_owned(), self.colsample_bynode.to_string()));
-        v.push(("lambda".to_owned(), self.lambda.to_string()));
-        v.push(("alpha".to_owned(), self.alpha.to_string()));
+        if self.lambda != defaults.lambda {
+            v.push(("lambda".to_owned(), self.lambda.to_string()));
+        }
+        if self.alpha != defaults.alpha {
+            v.push(("alpha".to_owned(), self.alpha.to_string()));
+        }
         v.push(("tree_method".to_owned(), self.tree_method.to_string()));
         v.push(("sketch_eps".to_owned(), self.sketch_eps.to_string()));
         v.push(("scale_pos_weight".to_owned(), self.scale_pos_weight.to_string()));
@@ -399,6 +485,18 @@ impl TreeBoosterParameters {
         v.push(("max_bin".to_owned(), self.max_bin.to_string()));
         v.push(("num_parallel_tree".to_owned(), self.num_parallel_tree.to_string()));
         v.push(("predictor".to_owned(), self.predictor.to_string()));
+        if let Some(max_cat_to_onehot) = self.max_cat_to_onehot {
+            v.push(("max_cat_to_onehot".to_owned(), max_cat_to_onehot.to_string()));
+        }
+        if let Some(max_cat_threshold) = self.max_cat_threshold {
+            v.push(("max_cat_threshold".to_owned(), max_cat_threshold.to_string()));
+        }
+        if let Some(ref sampling_method) = self.sampling_method {
+            v.push(("sampling_method".to_owned(), sampling_method.to_string()));
+        }
+        if let Some(max_cached_hist_node) = self.max_cached_hist_node {
+            v.push(("max_cached_hist_node".to_owned(), max_cached_hist_node.to_string()));
+        }
 
         // Don't pass anything to XGBoost if the user didn't specify anything.
         // This allows XGBoost to figure it out on it's own, and suppresses the
@@ -414,13 +512,35 @@ impl TreeBoosterParameters {
 }
 
 impl TreeBoosterParametersBuilder {
+    /// Set [`gamma`](#method.gamma), under its alternative name.
+    pub fn min_split_loss(&mut self, value: f32) -> &mut Self {
+        self.gamma(value)
+    }
+
+    /// Set [`lambda`](#method.lambda), under its alternative name.
+    pub fn reg_lambda(&mut self, value: f32) -> &mut Self {
+        self.lambda(value)
+    }
+
+    /// Set [`alpha`](#method.alpha), under its alternative name.
+    pub fn reg_alpha(&mut self, value: f32) -> &mut Self {
+        self.alpha(value)
+    }
+
     fn validate(&self) -> Result<(), String> {
         Interval::new_closed_closed(0.0, 1.0).validate(&self.eta, "eta")?;
+        Interval::new_closed_closed(0.0, f32::INFINITY).validate(&self.gamma, "gamma")?;
+        Interval::new_closed_closed(0.0, f32::INFINITY).validate(&self.min_child_weight, "min_child_weight")?;
+        Interval::new_closed_closed(0.0, f32::INFINITY).validate(&self.lambda, "lambda")?;
+        Interval::new_closed_closed(0.0, f32::INFINITY).validate(&self.alpha, "alpha")?;
         Interval::new_open_closed(0.0, 1.0).validate(&self.subsample, "subsample")?;
         Interval::new_open_closed(0.0, 1.0).validate(&self.colsample_bytree, "colsample_bytree")?;
         Interval::new_open_closed(0.0, 1.0).validate(&self.colsample_bylevel, "colsample_bylevel")?;
         Interval::new_open_closed(0.0, 1.0).validate(&self.colsample_bynode, "colsample_bynode")?;
         Interval::new_open_open(0.0, 1.0).validate(&self.sketch_eps, "sketch_eps")?;
+        if let Some(max_cached_hist_node) = self.max_cached_hist_node {
+            Interval::new_closed_closed(1, u32::MAX).validate(&max_cached_hist_node, "max_cached_hist_node")?;
+        }
         Ok(())
     }
 }
@@ -436,4 +556,68 @@ mod tests {
         let p = TreeBoosterParametersBuilder::default().build().unwrap();
         assert_eq!(p.eta, 0.3);
     }
+
+    #[test]
+    fn categorical_params() {
+        let p = TreeBoosterParameters::default();
+        assert!(!p.as_string_pairs().iter().any(|(k, _)| k.starts_with("max_cat")));
+
+        let p = TreeBoosterParametersBuilder::default()
+            .max_cat_to_onehot(Some(4))
+            .max_cat_threshold(Some(16))
+            .build()
+            .unwrap();
+        let pairs = p.as_string_pairs();
+        assert!(pairs.contains(&("max_cat_to_onehot".to_owned(), "4".to_owned())));
+        assert!(pairs.contains(&("max_cat_threshold".to_owned(), "16".to_owned())));
+    }
+
+    #[test]
+    fn hist_memory_params() {
+        let p = TreeBoosterParameters::default();
+        let pairs = p.as_string_pairs();
+        assert!(!pairs.iter().any(|(k, _)| k == "sampling_method" || k == "max_cached_hist_node"));
+
+        let p = TreeBoosterParametersBuilder::default()
+            .sampling_method(Some(SamplingMethod::GradientBased))
+            .max_cached_hist_node(Some(1024))
+            .build()
+            .unwrap();
+        let pairs = p.as_string_pairs();
+        assert!(pairs.contains(&("sampling_method".to_owned(), "gradient_based".to_owned())));
+        assert!(pairs.contains(&("max_cached_hist_node".to_owned(), "1024".to_owned())));
+
+        let result = TreeBoosterParametersBuilder::default()
+            .max_cached_hist_node(Some(0))
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn regularization_params() {
+        let p = TreeBoosterParameters::default();
+        let pairs = p.as_string_pairs();
+        let names = ["gamma", "max_depth", "min_child_weight", "lambda", "alpha"];
+        assert!(!pairs.iter().any(|(k, _)| names.contains(&k.as_str())));
+
+        let p = TreeBoosterParametersBuilder::default()
+            .min_split_loss(0.5)
+            .max_depth(3)
+            .min_child_weight(2.0)
+            .reg_lambda(10.0)
+            .reg_alpha(0.25)
+            .build()
+            .unwrap();
+        let pairs = p.as_string_pairs();
+        assert!(pairs.contains(&("gamma".to_owned(), "0.5".to_owned())));
+        assert!(pairs.contains(&("max_depth".to_owned(), "3".to_owned())));
+        assert!(pairs.contains(&("min_child_weight".to_owned(), "2".to_owned())));
+        assert!(pairs.contains(&("lambda".to_owned(), "10".to_owned())));
+        assert!(pairs.contains(&("alpha".to_owned(), "0.25".to_owned())));
+
+        assert!(TreeBoosterParametersBuilder::default().gamma(-1.0).build().is_err());
+        assert!(TreeBoosterParametersBuilder::default().min_child_weight(-1.0).build().is_err());
+        assert!(TreeBoosterParametersBuilder::default().lambda(f32::NAN).build().is_err());
+        assert!(TreeBoosterParametersBuilder::default().alpha(-0.1).build().is_err());
+    }
 }